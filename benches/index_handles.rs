@@ -0,0 +1,68 @@
+//! Measures the per call overhead of creating an index handle for every request, which shares the
+//! [`Client`]'s connection pool, against building a fresh `reqwest::Client` for every request.
+//!
+//! Both run against a local http server, so the numbers reflect connection setup rather than
+//! Pinecone itself.
+
+use std::{convert::Infallible, net::SocketAddr};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use hyper::{Body, Response, Server, service::{make_service_fn, service_fn}};
+use pinenut::Client;
+use tokio::runtime::Runtime;
+
+const STATS: &str = r#"{"namespaces":{},"dimension":4,"indexFullness":0,"totalVectorCount":0}"#;
+
+fn start_server(rt: &Runtime) -> SocketAddr {
+    rt.block_on(async {
+        let make_svc = make_service_fn(|_| async {
+            Ok::<_, Infallible>(service_fn(|req: hyper::Request<Body>| async move {
+                let body = match req.uri().path() {
+                    "/actions/whoami" => r#"{"project_name":"bench","user_label":null,"user_name":null}"#,
+                    _ => STATS
+                };
+                Ok::<_, Infallible>(Response::new(Body::from(body)))
+            }))
+        });
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_svc);
+        let addr = server.local_addr();
+        tokio::spawn(server);
+        addr
+    })
+}
+
+fn index_handles(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let addr = start_server(&rt);
+    let url = format!("http://{}", addr);
+    let client = rt.block_on(
+        Client::builder("bench")
+            .controller_url(&url)
+            .data_plane_url(format!("{}/{{index}}", url))
+            .build()
+    ).unwrap();
+
+    let mut group = c.benchmark_group("index_handle_per_call");
+    group.bench_function("shared_pool", |b| {
+        b.to_async(&rt).iter(|| async {
+            client.index("bench").describe_stats().await.unwrap().dimension
+        })
+    });
+    group.bench_function("fresh_pool", |b| {
+        b.to_async(&rt).iter(|| async {
+            reqwest::Client::new()
+                .get(format!("{}/bench/describe_index_stats", url))
+                .header("Api-Key", "bench")
+                .send()
+                .await
+                .unwrap()
+                .text()
+                .await
+                .unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, index_handles);
+criterion_main!(benches);
@@ -0,0 +1,10 @@
+fn main() {
+    #[cfg(feature = "grpc")]
+    {
+        // protoc is vendored so that building the grpc feature needs no system install.
+        std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path().expect("no vendored protoc for this platform"));
+        tonic_build::configure()
+            .compile(&["proto/vector_service.proto"], &["proto"])
+            .expect("failed to compile proto/vector_service.proto");
+    }
+}
@@ -0,0 +1,83 @@
+//! The commands managing indexes and collections.
+
+use std::io::Write;
+
+use pinenut::{
+    Client,
+    Result,
+    WaitOptions,
+    models::{IndexCreateRequest, IndexSpec, ServerlessSpec}
+};
+
+use crate::{
+    args::{Command, CreateIndexArgs, Output},
+    output::{print, Message, Names}
+};
+
+/// Runs one of the commands managing indexes and collections, handing the data commands to
+/// [`crate::data::run`].
+pub async fn run(client: &Client, command: Command, output: Output, out: &mut dyn Write) -> Result<()> {
+    match command {
+        Command::ListIndexes => print(out, output, &Names::new("INDEX", client.list_indexes().await?)),
+        Command::Describe{index} => print(out, output, &client.index(index).describe().await?),
+        Command::CreateIndex(args) => create_index(client, args, output, out).await,
+        Command::Configure{index, replicas, pod_type} => {
+            let response = client.index(index.as_str()).configure(replicas, pod_type).await?;
+            print(out, output, &Message::new(response, || format!("configuring index {}", index)))
+        },
+        Command::DeleteIndex{index} => {
+            let response = client.index(index.as_str()).delete().await?;
+            print(out, output, &Message::new(response, || format!("deleting index {}", index)))
+        },
+        Command::ListCollections => print(out, output, &Names::new("COLLECTION", client.list_collections().await?)),
+        Command::CreateCollection{name, source} => {
+            let response = client.create_collection(name.as_str(), source).await?;
+            print(out, output, &Message::new(response, || format!("creating collection {}", name)))
+        },
+        Command::DescribeCollection{name} => print(out, output, &client.describe_collection(name).await?),
+        Command::DeleteCollection{name} => {
+            let response = client.delete_collection(name.as_str()).await?;
+            print(out, output, &Message::new(response, || format!("deleting collection {}", name)))
+        },
+        Command::DescribeStats{index, filter} => {
+            let index = client.index(index);
+            let stats = match filter {
+                Some(filter) => index.describe_stats_filtered(filter).await?,
+                None => index.describe_stats().await?
+            };
+            print(out, output, &stats)
+        },
+        Command::Data(command) => crate::data::run(client, command, output, out).await
+    }
+}
+
+async fn create_index(client: &Client, args: CreateIndexArgs, output: Output, out: &mut dyn Write) -> Result<()> {
+    let mut request = IndexCreateRequest::builder(args.name.as_str(), args.dimension).metric(args.metric.into());
+    if let Some(pods) = args.pods {
+        request = request.pods(pods);
+    }
+    if let Some(replicas) = args.replicas {
+        request = request.replicas(replicas);
+    }
+    if let Some(shards) = args.shards {
+        request = request.shards(shards);
+    }
+    if let Some(pod_type) = args.pod_type {
+        request = request.pod_type(pod_type);
+    }
+    if !args.indexed.is_empty() {
+        request = request.metadata_config(args.indexed);
+    }
+    if let Some(collection) = args.source_collection {
+        request = request.source_collection(collection);
+    }
+    if let (Some(cloud), Some(region)) = (args.cloud, args.region) {
+        request = request.spec(IndexSpec::Serverless(ServerlessSpec{cloud: cloud.into(), region}));
+    }
+    let response = client.create_index(request.build()?).await?;
+    if args.wait {
+        let description = client.index(args.name).wait_until_ready(WaitOptions::default()).await?;
+        return print(out, output, &description);
+    }
+    print(out, output, &Message::new(response, || format!("creating index {}", args.name)))
+}
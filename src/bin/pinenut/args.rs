@@ -0,0 +1,317 @@
+//! The arguments of the command line tool.
+
+use std::path::PathBuf;
+
+use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
+use pinenut::{
+    ControlPlane,
+    models::{Cloud, Filter, MappedValue, Metric}
+};
+
+/// Manages Pinecone indexes, collections and vectors.
+///
+/// The api key is read from the profile given by --profile, from PINECONE_API_KEY, or from the
+/// default profile of the credentials file, in that order.
+#[derive(Debug, Parser)]
+#[command(name = "pinenut", version)]
+pub struct Cli {
+    #[command(flatten)]
+    pub connection: ConnectionArgs,
+
+    /// How results are printed.
+    #[arg(long, short, global = true, value_enum, default_value_t = Output::Table)]
+    pub output: Output,
+
+    #[command(subcommand)]
+    pub command: Command
+}
+
+/// Where the credentials come from and which control plane to talk to.
+#[derive(Debug, Args)]
+pub struct ConnectionArgs {
+    /// The profile of the credentials file to use.
+    #[arg(long, global = true, env = "PINENUT_PROFILE")]
+    pub profile: Option<String>,
+
+    /// The credentials file, `~/.config/pinenut` by default.
+    #[arg(long, global = true, env = "PINENUT_CREDENTIALS")]
+    pub credentials: Option<PathBuf>,
+
+    /// The environment of the legacy control plane, overriding the credentials.
+    #[arg(long, global = true)]
+    pub environment: Option<String>,
+
+    /// The control plane to talk to, overriding the profile.
+    #[arg(long, global = true, value_enum)]
+    pub control_plane: Option<ControlPlaneArg>,
+
+    /// Overrides the controller url, e.g. for a local stand-in of Pinecone.
+    #[arg(long, global = true)]
+    pub controller_url: Option<String>,
+
+    /// Overrides the base url of every index's data plane, `{index}` is replaced by the index name.
+    #[arg(long, global = true)]
+    pub data_plane_url: Option<String>
+}
+
+/// The format results are printed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Output {
+    /// Aligned columns meant to be read.
+    Table,
+    /// Pretty printed json meant to be processed.
+    Json
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Lists the names of every index.
+    ListIndexes,
+
+    /// Describes an index.
+    Describe {
+        /// The name of the index.
+        index: String
+    },
+
+    /// Creates an index.
+    CreateIndex(CreateIndexArgs),
+
+    /// Changes the replicas and pod type of a pod based index.
+    Configure {
+        /// The name of the index.
+        index: String,
+        /// The number of replicas.
+        #[arg(long)]
+        replicas: usize,
+        /// The type of pod, such as `p1.x2`.
+        #[arg(long)]
+        pod_type: String
+    },
+
+    /// Deletes an index.
+    DeleteIndex {
+        /// The name of the index.
+        index: String
+    },
+
+    /// Lists the names of every collection.
+    ListCollections,
+
+    /// Creates a collection from an index.
+    CreateCollection {
+        /// The name of the collection.
+        name: String,
+        /// The index to copy into the collection.
+        #[arg(long)]
+        source: String
+    },
+
+    /// Describes a collection.
+    DescribeCollection {
+        /// The name of the collection.
+        name: String
+    },
+
+    /// Deletes a collection.
+    DeleteCollection {
+        /// The name of the collection.
+        name: String
+    },
+
+    /// Describes the number of vectors within an index and its namespaces.
+    DescribeStats {
+        /// The name of the index.
+        index: String,
+        /// Only counts the vectors matching this metadata filter, given as json.
+        #[arg(long, value_parser = parse_filter)]
+        filter: Option<Filter>
+    },
+
+    #[command(flatten)]
+    Data(DataCommand)
+}
+
+/// The commands reading and writing the vectors of an index.
+#[derive(Debug, Subcommand)]
+pub enum DataCommand {
+    /// Searches a namespace for the vectors most similar to a vector or to a stored vector.
+    Query(QueryArgs),
+
+    /// Fetches vectors by id.
+    Fetch {
+        /// The name of the index.
+        index: String,
+        /// The ids of the vectors.
+        #[arg(required = true)]
+        ids: Vec<String>,
+        /// The namespace of the vectors, the default namespace if not given.
+        #[arg(long)]
+        namespace: Option<String>
+    },
+
+    /// Sets metadata fields of a vector, leaving its other fields as they are.
+    Update {
+        /// The name of the index.
+        index: String,
+        /// The id of the vector.
+        id: String,
+        /// The metadata fields to set, given as a json object.
+        #[arg(long, value_parser = parse_metadata)]
+        metadata: MappedValue,
+        /// The namespace of the vector, the default namespace if not given.
+        #[arg(long)]
+        namespace: Option<String>
+    },
+
+    /// Upserts the vectors of a JSON Lines or CSV file, `-` reads standard input.
+    ///
+    /// Every line of a JSON Lines file is a json encoded vector, as written by an export. A CSV
+    /// file starts with a header row naming an `id` and a `values` column, the values being
+    /// numbers separated by spaces, every other column is stored as a metadata field.
+    Upsert {
+        /// The name of the index.
+        index: String,
+        /// The file holding the vectors.
+        file: PathBuf,
+        /// The format of the file, guessed from its extension by default.
+        #[arg(long, value_enum)]
+        format: Option<InputFormat>,
+        /// The namespace to upsert into, the default namespace if not given.
+        #[arg(long, default_value = "")]
+        namespace: String
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(group(ArgGroup::new("query").required(true).args(["id", "vector_file"])))]
+pub struct QueryArgs {
+    /// The name of the index.
+    pub index: String,
+    /// Queries with the values of the stored vector with this id.
+    #[arg(long)]
+    pub id: Option<String>,
+    /// Queries with the vector of this file, a json array of numbers. `-` reads standard input.
+    #[arg(long)]
+    pub vector_file: Option<PathBuf>,
+    /// The number of matches to return.
+    #[arg(long, default_value_t = 10)]
+    pub top_k: usize,
+    /// The namespace to search, the default namespace if not given.
+    #[arg(long)]
+    pub namespace: Option<String>,
+    /// Only matches the vectors matching this metadata filter, given as json.
+    #[arg(long, value_parser = parse_filter)]
+    pub filter: Option<Filter>,
+    /// Includes the values of the matches.
+    #[arg(long)]
+    pub include_values: bool,
+    /// Includes the metadata of the matches.
+    #[arg(long)]
+    pub include_metadata: bool
+}
+
+/// The formats vectors can be upserted from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum InputFormat {
+    Jsonl,
+    Csv
+}
+
+#[derive(Debug, Args)]
+pub struct CreateIndexArgs {
+    /// The name of the index.
+    pub name: String,
+    /// The dimension of the vectors.
+    #[arg(long)]
+    pub dimension: usize,
+    /// The similarity metric.
+    #[arg(long, value_enum, default_value_t = MetricArg::Cosine)]
+    pub metric: MetricArg,
+    /// Number of pods.
+    #[arg(long)]
+    pub pods: Option<usize>,
+    /// Number of replicas.
+    #[arg(long)]
+    pub replicas: Option<usize>,
+    /// Number of shards.
+    #[arg(long)]
+    pub shards: Option<usize>,
+    /// The type of pod, such as `p1.x1`.
+    #[arg(long)]
+    pub pod_type: Option<String>,
+    /// Only indexes these metadata fields, separated by commas.
+    #[arg(long, value_delimiter = ',')]
+    pub indexed: Vec<String>,
+    /// Creates the index from a collection.
+    #[arg(long)]
+    pub source_collection: Option<String>,
+    /// Creates a serverless index in this cloud, requires --region.
+    #[arg(long, value_enum, requires = "region")]
+    pub cloud: Option<CloudArg>,
+    /// The region of a serverless index.
+    #[arg(long, requires = "cloud")]
+    pub region: Option<String>,
+    /// Waits until the index is ready.
+    #[arg(long)]
+    pub wait: bool
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ControlPlaneArg {
+    Legacy,
+    Global
+}
+
+impl From<ControlPlaneArg> for ControlPlane {
+    fn from(value: ControlPlaneArg) -> Self {
+        match value {
+            ControlPlaneArg::Legacy => ControlPlane::Legacy,
+            ControlPlaneArg::Global => ControlPlane::Global
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum MetricArg {
+    Cosine,
+    Euclidean,
+    Dotproduct
+}
+
+impl From<MetricArg> for Metric {
+    fn from(value: MetricArg) -> Self {
+        match value {
+            MetricArg::Cosine => Metric::COSINE,
+            MetricArg::Euclidean => Metric::EUCLIDEAN,
+            MetricArg::Dotproduct => Metric::DOTPRODUCT
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CloudArg {
+    Aws,
+    Gcp,
+    Azure
+}
+
+impl From<CloudArg> for Cloud {
+    fn from(value: CloudArg) -> Self {
+        match value {
+            CloudArg::Aws => Cloud::AWS,
+            CloudArg::Gcp => Cloud::GCP,
+            CloudArg::Azure => Cloud::AZURE
+        }
+    }
+}
+
+/// Parses a metadata filter given as a json object.
+pub fn parse_filter(filter: &str) -> Result<Filter, String> {
+    serde_json::from_str(filter).map_err(|err| format!("the filter isn't a json object: {}", err))
+}
+
+/// Parses metadata given as a json object.
+pub fn parse_metadata(metadata: &str) -> Result<MappedValue, String> {
+    serde_json::from_str(metadata).map_err(|err| format!("the metadata isn't a json object: {}", err))
+}
@@ -0,0 +1,183 @@
+//! The commands reading and writing the vectors of an index.
+
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Read, Write},
+    path::Path
+};
+
+use futures::io::AllowStdIo;
+use pinenut::{
+    Client,
+    Error,
+    Index,
+    Result,
+    batch::{BatchOptions, BatchUpsertResponse},
+    models::{FetchRequest, MappedValue, QueryRequest, UpdateRequest, Vector}
+};
+use serde_json::Value;
+
+use crate::{
+    args::{DataCommand, InputFormat, Output, QueryArgs},
+    output::{print, Message, Upserted}
+};
+
+/// Runs one of the commands reading and writing vectors.
+pub async fn run(client: &Client, command: DataCommand, output: Output, out: &mut dyn Write) -> Result<()> {
+    match command {
+        DataCommand::Query(args) => query(client, args, output, out).await,
+        DataCommand::Fetch{index, ids, namespace} => {
+            let response = client.index(index).fetch(FetchRequest{ids, namespace}).await?;
+            print(out, output, &response)
+        },
+        DataCommand::Update{index, id, metadata, namespace} => {
+            let request = UpdateRequest {
+                id: id.clone(),
+                metadata: Some(metadata),
+                namespace,
+                ..Default::default()
+            };
+            client.index(index).update(request).await?;
+            print(out, output, &Message::new(String::new(), || format!("updated vector {}", id)))
+        },
+        DataCommand::Upsert{index, file, format, namespace} => {
+            let format = format.unwrap_or_else(|| guess_format(&file));
+            let response = upsert(&client.index(index), &file, format, namespace).await?;
+            print(out, output, &Upserted::new(&response))?;
+            match response.failures.into_iter().next() {
+                Some(failure) => Err(failure.error),
+                None => Ok(())
+            }
+        }
+    }
+}
+
+async fn query(client: &Client, args: QueryArgs, output: Output, out: &mut dyn Write) -> Result<()> {
+    let vector = match args.vector_file {
+        Some(ref path) => Some(read_vector(path)?),
+        None => None
+    };
+    let request = QueryRequest {
+        namespace: args.namespace,
+        top_k: args.top_k,
+        filter: args.filter,
+        include_values: args.include_values,
+        include_metadata: args.include_metadata,
+        vector,
+        sparse_vector: None,
+        id: args.id
+    };
+    print(out, output, &client.index(args.index).query(request).await?)
+}
+
+/// Opens `path` for reading, standard input when it's `-`.
+fn open(path: &Path) -> Result<Box<dyn BufRead>> {
+    if path == Path::new("-") {
+        return Ok(Box::new(io::stdin().lock()));
+    }
+    let file = File::open(path).map_err(Error::IoError)?;
+    Ok(Box::new(BufReader::new(file)))
+}
+
+/// Reads a query vector, a json array of numbers.
+fn read_vector(path: &Path) -> Result<Vec<f32>> {
+    let mut text = String::new();
+    open(path)?.read_to_string(&mut text).map_err(Error::IoError)?;
+    serde_json::from_str(&text).map_err(|err| Error::ArgumentError {
+        name: path.display().to_string(),
+        found: err.to_string(),
+        expected: "a json array of numbers".to_string()
+    })
+}
+
+fn guess_format(path: &Path) -> InputFormat {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(extension) if extension.eq_ignore_ascii_case("csv") => InputFormat::Csv,
+        _ => InputFormat::Jsonl
+    }
+}
+
+async fn upsert(index: &Index, path: &Path, format: InputFormat, namespace: String) -> Result<BatchUpsertResponse> {
+    let reader = open(path)?;
+    // the tool runs a single command, so reading the file blocking its worker thread is harmless.
+    match format {
+        InputFormat::Jsonl => index.import_jsonl(namespace, AllowStdIo::new(reader), BatchOptions::default(), |_| {}).await,
+        InputFormat::Csv => index.upsert_batched(namespace, read_csv(reader)?, BatchOptions::default()).await
+    }
+}
+
+/// Reads vectors from CSV with an `id` and a `values` column, every other column being a metadata
+/// field. Metadata cells holding a number or a boolean are stored as such, others as strings and
+/// empty cells are left out.
+fn read_csv(reader: impl Read) -> Result<Vec<Vector>> {
+    let mut reader = csv::Reader::from_reader(reader);
+    let headers = reader.headers().map_err(csv_error)?.clone();
+    let column = |name: &str| headers.iter().position(|h| h == name).ok_or_else(|| Error::ArgumentError {
+        name: "csv header".to_string(),
+        found: headers.iter().collect::<Vec<_>>().join(","),
+        expected: format!("a {} column", name)
+    });
+    let (id, values) = (column("id")?, column("values")?);
+    let mut vectors = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(csv_error)?;
+        let line = record.position().map(|p| p.line()).unwrap_or_default();
+        let parsed = record[values].split_whitespace()
+            .map(str::parse)
+            .collect::<std::result::Result<Vec<f32>, _>>()
+            .map_err(|err| Error::ArgumentError {
+                name: format!("line {}", line),
+                found: err.to_string(),
+                expected: "values separated by spaces".to_string()
+            })?;
+        let metadata: MappedValue = headers.iter().zip(&record).enumerate()
+            .filter(|(i, (_, cell))| *i != id && *i != values && !cell.is_empty())
+            .map(|(_, (name, cell))| (name.to_string(), metadata_value(cell)))
+            .collect();
+        vectors.push(Vector {
+            id: record[id].to_string(),
+            values: parsed,
+            sparse_values: None,
+            metadata: (!metadata.is_empty()).then_some(metadata)
+        });
+    }
+    Ok(vectors)
+}
+
+fn metadata_value(cell: &str) -> Value {
+    match serde_json::from_str(cell) {
+        Ok(value @ (Value::Number(_) | Value::Bool(_))) => value,
+        _ => Value::String(cell.to_string())
+    }
+}
+
+fn csv_error(err: csv::Error) -> Error {
+    let line = err.position().map(|p| p.line()).unwrap_or_default();
+    Error::ArgumentError {
+        name: format!("line {}", line),
+        found: err.to_string(),
+        expected: "a CSV record".to_string()
+    }
+}
+
+#[cfg(test)]
+mod data_test {
+
+    use super::*;
+
+    #[test]
+    fn test_read_csv() {
+        let vectors = read_csv("values,id,genre,year,seen\n1 2.5,a,drama,1999,true\n,b,,,\n".as_bytes()).unwrap();
+        assert_eq!(vectors[0].id, "a");
+        assert_eq!(vectors[0].values, vec![1.0, 2.5]);
+        let metadata = vectors[0].metadata.as_ref().unwrap();
+        assert_eq!(metadata["genre"], "drama");
+        assert_eq!(metadata["year"], 1999);
+        assert_eq!(metadata["seen"], true);
+        assert!(vectors[1].values.is_empty() && vectors[1].metadata.is_none());
+
+        assert!(matches!(read_csv("id,vector\na,1\n".as_bytes()), Err(Error::ArgumentError{..})));
+        let invalid = read_csv("id,values\na,1\nb,x\n".as_bytes()).err().unwrap();
+        assert!(matches!(invalid, Error::ArgumentError{ref name, ..} if name == "line 3"));
+    }
+}
@@ -0,0 +1,209 @@
+//! `pinenut`, a command line tool managing Pinecone indexes, collections and vectors through
+//! [`pinenut::Client`]. Run `pinenut --help` for the list of commands.
+
+mod admin;
+mod args;
+mod data;
+mod output;
+
+use std::{io::Write, process::ExitCode};
+
+use clap::Parser;
+use pinenut::{
+    Client,
+    ClientBuilder,
+    Error,
+    Result,
+    credentials::{EnvCredentials, Profile, API_KEY_VAR}
+};
+
+use args::{Cli, ConnectionArgs};
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let mut out = std::io::stdout().lock();
+    match run(cli, &mut out).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", describe(&err));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(cli: Cli, out: &mut dyn Write) -> Result<()> {
+    let client = connect(&cli.connection).await?;
+    admin::run(&client, cli.command, cli.output, out).await
+}
+
+/// Builds a [`Client`] from the profile given by `--profile`, from the environment or from the
+/// default profile, applying the connection flags on top.
+async fn connect(args: &ConnectionArgs) -> Result<Client> {
+    let mut builder = match args.profile {
+        None if std::env::var_os(API_KEY_VAR).is_some() => Client::builder_with_credentials(EnvCredentials::new()),
+        ref profile => profile_builder(args, profile.as_deref().unwrap_or(Profile::DEFAULT))?
+    };
+    if let Some(ref environment) = args.environment {
+        builder = builder.environment(environment);
+    }
+    if let Some(control_plane) = args.control_plane {
+        builder = builder.control_plane(control_plane.into());
+    }
+    if let Some(ref url) = args.controller_url {
+        builder = builder.controller_url(url);
+    }
+    if let Some(ref url) = args.data_plane_url {
+        builder = builder.data_plane_url(url);
+    }
+    builder.user_agent(concat!("pinenut-cli/", env!("CARGO_PKG_VERSION"))).build().await
+}
+
+fn profile_builder(args: &ConnectionArgs, name: &str) -> Result<ClientBuilder> {
+    let path = match args.credentials {
+        Some(ref path) => path.clone(),
+        None => Profile::default_path()
+            .ok_or_else(|| Error::CredentialsError(format!("{} isn't set and there is no home directory", API_KEY_VAR)))?
+    };
+    if args.profile.is_none() && !path.exists() {
+        return Err(Error::CredentialsError(format!("set {} or add a [{}] profile to {}", API_KEY_VAR, name, path.display())));
+    }
+    Ok(Client::builder_with_credentials(Profile::load_from(&path, name)?))
+}
+
+/// The message of an error, including the cause of http errors which isn't part of it.
+fn describe(err: &Error) -> String {
+    match err {
+        Error::ReqwestError(cause) | Error::ReqwestResponseError(_, cause) => format!("{}: {}", err, cause),
+        err => err.to_string()
+    }
+}
+
+#[cfg(test)]
+mod cli_test {
+
+    use std::path::{Path, PathBuf};
+
+    use pinenut::mock::MockPinecone;
+
+    use super::*;
+
+    /// Writes a credentials file whose default profile points at `mock`.
+    fn credentials(mock: &MockPinecone, name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("pinenut-cli-{}-{}", name, std::process::id()));
+        let profile = format!("[default]\napi_key = \"{}\"\ncontroller_url = \"{}\"\n", mock.api_key(), mock.url());
+        std::fs::write(&path, profile).unwrap();
+        path
+    }
+
+    async fn pinenut(path: &Path, args: &[&str]) -> Result<String> {
+        let mut argv = vec!["pinenut", "--credentials", path.to_str().unwrap()];
+        if !args.contains(&"--profile") {
+            argv.extend(["--profile", "default"]);
+        }
+        argv.extend(args);
+        let mut out = Vec::new();
+        run(Cli::try_parse_from(argv).unwrap(), &mut out).await?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_index_commands() {
+        let mock = MockPinecone::start().await;
+        let path = credentials(&mock, "index");
+        pinenut(&path, &["create-index", "movies", "--dimension", "4", "--metric", "dotproduct"]).await.unwrap();
+        pinenut(&path, &["create-index", "books", "--dimension", "8"]).await.unwrap();
+
+        let list = pinenut(&path, &["list-indexes", "--output", "json"]).await.unwrap();
+        assert_eq!(serde_json::from_str::<Vec<String>>(&list).unwrap(), vec!["books", "movies"]);
+        assert_eq!(pinenut(&path, &["list-indexes"]).await.unwrap(), "INDEX\nbooks\nmovies\n");
+
+        let description = pinenut(&path, &["describe", "movies"]).await.unwrap();
+        assert!(description.contains("dimension  4\n"));
+        assert!(description.contains("metric     dotproduct\n"));
+
+        let stats = pinenut(&path, &["describe-stats", "movies", "-o", "json"]).await.unwrap();
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&stats).unwrap()["dimension"], 4);
+        let filtered = pinenut(&path, &["describe-stats", "movies", "--filter", r#"{"genre":"drama"}"#]).await.unwrap();
+        assert!(filtered.contains("total_vector_count  0\n"));
+
+        pinenut(&path, &["delete-index", "books"]).await.unwrap();
+        let missing = pinenut(&path, &["describe", "books"]).await;
+        assert!(matches!(missing, Err(Error::NotFound(_))));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_collection_commands() {
+        let mock = MockPinecone::start().await;
+        let path = credentials(&mock, "collection");
+        pinenut(&path, &["create-index", "movies", "--dimension", "4"]).await.unwrap();
+        pinenut(&path, &["create-collection", "backup", "--source", "movies"]).await.unwrap();
+        assert_eq!(pinenut(&path, &["list-collections"]).await.unwrap(), "COLLECTION\nbackup\n");
+        let description = pinenut(&path, &["describe-collection", "backup", "-o", "json"]).await.unwrap();
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&description).unwrap()["name"], "backup");
+        pinenut(&path, &["delete-collection", "backup"]).await.unwrap();
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_vector_commands() {
+        let mock = MockPinecone::start().await;
+        let path = credentials(&mock, "vector");
+        let dir = std::env::temp_dir();
+        let csv = dir.join(format!("pinenut-cli-{}.csv", std::process::id()));
+        let jsonl = dir.join(format!("pinenut-cli-{}.jsonl", std::process::id()));
+        let query = dir.join(format!("pinenut-cli-query-{}.json", std::process::id()));
+        std::fs::write(&csv, "id,values,genre,year\nmovie-1,1 0 0,drama,1999\nmovie-2,0 1 0,comedy,2004\n").unwrap();
+        std::fs::write(&jsonl, "{\"id\":\"movie-3\",\"values\":[0,0,1],\"metadata\":{\"genre\":\"drama\"}}\n").unwrap();
+        std::fs::write(&query, "[1, 0.1, 0]").unwrap();
+        pinenut(&path, &["create-index", "movies", "--dimension", "3"]).await.unwrap();
+
+        let upserted = pinenut(&path, &["upsert", "movies", csv.to_str().unwrap()]).await.unwrap();
+        assert_eq!(upserted, "upserted_count  2\nfailed_count    0\n");
+        pinenut(&path, &["upsert", "movies", jsonl.to_str().unwrap(), "--format", "jsonl"]).await.unwrap();
+
+        let fetched = pinenut(&path, &["fetch", "movies", "movie-1", "movie-3", "-o", "json"]).await.unwrap();
+        let fetched: serde_json::Value = serde_json::from_str(&fetched).unwrap();
+        assert_eq!(fetched["vectors"]["movie-1"]["metadata"]["year"], 1999);
+        assert_eq!(fetched["vectors"]["movie-3"]["values"], serde_json::json!([0.0, 0.0, 1.0]));
+
+        pinenut(&path, &["update", "movies", "movie-2", "--metadata", r#"{"genre":"drama"}"#]).await.unwrap();
+        let fetched = pinenut(&path, &["fetch", "movies", "movie-2"]).await.unwrap();
+        assert!(fetched.contains(r#""genre":"drama""#));
+
+        let by_vector = pinenut(&path, &["query", "movies", "--vector-file", query.to_str().unwrap(), "--top-k", "2", "-o", "json"]).await.unwrap();
+        let by_vector: serde_json::Value = serde_json::from_str(&by_vector).unwrap();
+        assert_eq!(by_vector["matches"].as_array().unwrap().len(), 2);
+        assert_eq!(by_vector["matches"][0]["id"], "movie-1");
+
+        let filtered = pinenut(&path, &["query", "movies", "--id", "movie-1", "--filter", r#"{"year":{"$gt":2000}}"#, "--include-metadata"]).await.unwrap();
+        assert!(filtered.starts_with("ID       SCORE"));
+        assert!(filtered.contains("movie-2") && !filtered.contains("movie-3"));
+
+        for file in [path, csv, jsonl, query] {
+            std::fs::remove_file(file).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_credentials() {
+        let mock = MockPinecone::start().await;
+        let path = credentials(&mock, "credentials");
+        let missing = pinenut(&path, &["--profile", "staging", "list-indexes"]).await;
+        assert!(matches!(missing, Err(Error::CredentialsError(_))));
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(pinenut(&path, &["list-indexes"]).await, Err(Error::IoError(_))));
+    }
+
+    #[test]
+    fn test_arguments() {
+        assert!(Cli::try_parse_from(["pinenut", "create-index", "movies", "--dimension", "4", "--cloud", "aws"]).is_err());
+        assert!(Cli::try_parse_from(["pinenut", "describe-stats", "movies", "--filter", "[1]"]).is_err());
+        assert!(Cli::try_parse_from(["pinenut", "configure", "movies", "--replicas", "2", "--pod-type", "p1.x2"]).is_ok());
+        assert!(Cli::try_parse_from(["pinenut", "query", "movies", "--top-k", "3"]).is_err());
+        assert!(Cli::try_parse_from(["pinenut", "query", "movies", "--id", "a", "--vector-file", "q.json"]).is_err());
+        assert!(Cli::try_parse_from(["pinenut", "update", "movies", "a", "--metadata", "[1]"]).is_err());
+        assert!(Cli::try_parse_from(["pinenut", "fetch", "movies"]).is_err());
+    }
+}
@@ -0,0 +1,257 @@
+//! Prints results either as aligned tables or as json, see [`Output`].
+
+use std::{fmt, io::Write};
+
+use pinenut::{
+    Error,
+    Result,
+    batch::BatchUpsertResponse,
+    models::{CollectionDescription, FetchResponse, IndexDescription, IndexStats, MappedValue, QueryResponse}
+};
+use serde::Serialize;
+
+use crate::args::Output;
+
+/// Rows of cells printed in aligned columns, below a header row when it has one.
+#[derive(Debug, Default)]
+pub struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>
+}
+
+impl Table {
+
+    pub fn new(headers: &[&str]) -> Table {
+        Table {
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            rows: Vec::new()
+        }
+    }
+
+    /// A table of field names and values, without headers.
+    pub fn fields<'a>(fields: impl IntoIterator<Item = (&'a str, String)>) -> Table {
+        Table {
+            headers: Vec::new(),
+            rows: fields.into_iter().map(|(name, value)| vec![name.to_string(), value]).collect()
+        }
+    }
+
+    pub fn row(&mut self, cells: Vec<String>) {
+        self.rows.push(cells);
+    }
+}
+
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lines = (!self.headers.is_empty()).then_some(&self.headers).into_iter().chain(&self.rows);
+        let mut widths = Vec::new();
+        for line in lines.clone() {
+            widths.resize(widths.len().max(line.len()), 0);
+            for (width, cell) in widths.iter_mut().zip(line) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        for line in lines {
+            let mut text = String::new();
+            for (cell, width) in line.iter().zip(&widths) {
+                text.push_str(&format!("{:<width$}  ", cell, width = width));
+            }
+            writeln!(f, "{}", text.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+/// A result which can be printed in both formats, as one or more tables or as json.
+pub trait Render: Serialize {
+    fn tables(&self) -> Vec<Table>;
+}
+
+/// Prints `value` to `out` in the `output` format.
+pub fn print<T: Render>(out: &mut dyn Write, output: Output, value: &T) -> Result<()> {
+    let text = match output {
+        Output::Json => serde_json::to_string_pretty(value).map_err(Error::JsonError)? + "\n",
+        Output::Table => value.tables().iter().map(Table::to_string).collect::<Vec<_>>().join("\n")
+    };
+    out.write_all(text.as_bytes()).map_err(Error::IoError)
+}
+
+/// A list of names, printed as a json array.
+#[derive(Debug, Serialize)]
+#[serde(transparent)]
+pub struct Names {
+    #[serde(skip)]
+    header: &'static str,
+    names: Vec<String>
+}
+
+impl Names {
+    pub fn new(header: &'static str, mut names: Vec<String>) -> Names {
+        names.sort();
+        Names{header, names}
+    }
+}
+
+impl Render for Names {
+    fn tables(&self) -> Vec<Table> {
+        let mut table = Table::new(&[self.header]);
+        for name in &self.names {
+            table.row(vec![name.clone()]);
+        }
+        vec![table]
+    }
+}
+
+/// The text Pinecone answers some operations with, or a confirmation when it's empty.
+#[derive(Debug, Serialize)]
+pub struct Message {
+    message: String
+}
+
+impl Message {
+    pub fn new(response: String, done: impl FnOnce() -> String) -> Message {
+        let response = response.trim();
+        Message {
+            message: match response.is_empty() {
+                true => done(),
+                false => response.to_string()
+            }
+        }
+    }
+}
+
+impl Render for Message {
+    fn tables(&self) -> Vec<Table> {
+        vec![Table::fields([("message", self.message.clone())])]
+    }
+}
+
+/// The number of vectors an upsert stored and the ids of those it failed to.
+#[derive(Debug, Serialize)]
+pub struct Upserted {
+    upserted_count: usize,
+    failed_ids: Vec<String>
+}
+
+impl Upserted {
+    pub fn new(response: &BatchUpsertResponse) -> Upserted {
+        Upserted {
+            upserted_count: response.response.upserted_count,
+            failed_ids: response.failures.iter().flat_map(|f| f.ids.iter().cloned()).collect()
+        }
+    }
+}
+
+impl Render for Upserted {
+    fn tables(&self) -> Vec<Table> {
+        vec![Table::fields([
+            ("upserted_count", self.upserted_count.to_string()),
+            ("failed_count", self.failed_ids.len().to_string())
+        ])]
+    }
+}
+
+fn optional<T: ToString>(value: &Option<T>) -> String {
+    value.as_ref().map(ToString::to_string).unwrap_or_default()
+}
+
+impl Render for IndexDescription {
+    fn tables(&self) -> Vec<Table> {
+        let database = &self.database;
+        let status = &self.status;
+        vec![Table::fields([
+            ("name", database.name.clone()),
+            ("dimension", database.dimension.to_string()),
+            ("metric", database.metric.to_string()),
+            ("pods", database.pods.to_string()),
+            ("replicas", database.replicas.to_string()),
+            ("shards", database.shards.to_string()),
+            ("pod_type", optional(&database.pod_type)),
+            ("host", optional(&status.host)),
+            ("state", status.state.to_string()),
+            ("ready", status.ready.to_string())
+        ])]
+    }
+}
+
+impl Render for CollectionDescription {
+    fn tables(&self) -> Vec<Table> {
+        vec![Table::fields([
+            ("name", self.name.clone()),
+            ("status", self.status.clone()),
+            ("size", self.size.to_string()),
+            ("dimension", optional(&self.dimension)),
+            ("vector_count", optional(&self.vector_count)),
+            ("environment", optional(&self.environment))
+        ])]
+    }
+}
+
+impl Render for IndexStats {
+    fn tables(&self) -> Vec<Table> {
+        let summary = Table::fields([
+            ("dimension", self.dimension.to_string()),
+            ("index_fullness", self.index_fullness.to_string()),
+            ("total_vector_count", self.total_vector_count.to_string())
+        ]);
+        let mut namespaces = Table::new(&["NAMESPACE", "VECTORS"]);
+        let mut names: Vec<_> = self.namespaces.iter().collect();
+        names.sort_by_key(|(name, _)| name.as_str());
+        for (name, namespace) in names {
+            namespaces.row(vec![name.clone(), namespace.vector_count.to_string()]);
+        }
+        vec![summary, namespaces]
+    }
+}
+
+/// Values and metadata are printed as compact json, empty when not included.
+fn json<T: Serialize>(value: &Option<T>) -> String {
+    value.as_ref().and_then(|v| serde_json::to_string(v).ok()).unwrap_or_default()
+}
+
+impl Render for QueryResponse {
+    fn tables(&self) -> Vec<Table> {
+        let mut table = Table::new(&["ID", "SCORE", "VALUES", "METADATA"]);
+        for m in &self.matches {
+            table.row(vec![m.id.clone(), optional(&m.score), json(&m.values), json(&m.metadata)]);
+        }
+        vec![table]
+    }
+}
+
+impl Render for FetchResponse {
+    fn tables(&self) -> Vec<Table> {
+        let mut table = Table::new(&["ID", "VALUES", "METADATA"]);
+        for (id, vector) in &self.vectors {
+            table.row(vec![id.clone(), json(&Some(&vector.values)), json::<MappedValue>(&vector.metadata)]);
+        }
+        vec![table]
+    }
+}
+
+#[cfg(test)]
+mod output_test {
+
+    use super::*;
+
+    #[test]
+    fn test_table() {
+        let mut table = Table::new(&["NAME", "VECTORS"]);
+        table.row(vec!["movies".to_string(), "12".to_string()]);
+        table.row(vec!["a".to_string(), "3".to_string()]);
+        assert_eq!(table.to_string(), "NAME    VECTORS\nmovies  12\na       3\n");
+        assert_eq!(Table::fields([("name", "movies".to_string())]).to_string(), "name  movies\n");
+    }
+
+    #[test]
+    fn test_formats() {
+        let names = Names::new("INDEX", vec!["b".to_string(), "a".to_string()]);
+        let mut out = Vec::new();
+        print(&mut out, Output::Json, &names).unwrap();
+        assert_eq!(serde_json::from_slice::<Vec<String>>(&out).unwrap(), vec!["a", "b"]);
+
+        let mut out = Vec::new();
+        print(&mut out, Output::Table, &Message::new(String::new(), || "deleted movies".to_string())).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "message  deleted movies\n");
+    }
+}
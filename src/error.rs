@@ -1,8 +1,8 @@
 //! pinecone-wasm Errors
 
 use crate::rest::models::PineconeErrorResponse;
-use reqwest::{Method, Response, StatusCode};
-use std::result;
+use reqwest::{Method, StatusCode};
+use std::{fmt, result, time::Duration};
 use thiserror::Error as ThisError;
 
 /// result allias where the Err term is pine-client::Error
@@ -32,13 +32,39 @@ pub enum Error {
     #[error("Reqwest Response error")]
     ReqwestResponseError(StatusCode, reqwest::Error),
 
-    /// When pinecone sends their [`PineconeErrorResponse`] to the client.
-    #[error("Finetune Failed with Response with Status Code {0:?} {1:?} {2:?}")]
-    PineconeResponseError(StatusCode, Option<PineconeErrorResponse>, Option<String>),
+    /// The index, collection or vector the request targets doesn't exist, status 404.
+    #[error("Not found, {0}")]
+    NotFound(Box<ResponseError>),
 
-    /// This error is used when Pinecone fails to return a [`PineconeErrorResponse`]
-    #[error("This request has failed")]
-    PineconeError(Response),
+    /// The index or collection being created already exists, status 409.
+    #[error("Already exists, {0}")]
+    AlreadyExists(Box<ResponseError>),
+
+    /// The api key is invalid or isn't allowed to make the request, status 401 or 403.
+    #[error("Unauthorized, {0}")]
+    Unauthorized(Box<ResponseError>),
+
+    /// Too many requests were sent, status 429. The request can be sent again once
+    /// [`ResponseError::retry_after`] has passed, when Pinecone gives it.
+    #[error("Rate limited, {0}")]
+    RateLimited(Box<ResponseError>),
+
+    /// A quota of the project, such as its number of pods or indexes, has been reached. Sending the
+    /// request again won't succeed until the quota is raised or resources are freed.
+    #[error("Quota exceeded, {0}")]
+    QuotaExceeded(Box<ResponseError>),
+
+    /// Pinecone rejected the request as invalid, status 400, 412, 413 or 422.
+    #[error("Invalid request, {0}")]
+    InvalidRequest(Box<ResponseError>),
+
+    /// Pinecone failed to handle the request, a 5xx status.
+    #[error("Server error, {0}")]
+    ServerError(Box<ResponseError>),
+
+    /// Pinecone responded with any other error status.
+    #[error("Request failed, {0}")]
+    PineconeResponseError(Box<ResponseError>),
 
     /// An error that describes an incorrectly sized vector.
     #[error("Vector of id {id} had dimension {found} expected dimension size of {expected}")]
@@ -51,10 +77,64 @@ pub enum Error {
         id: String,
     },
 
+    /// An error reading or writing a file or stream, such as the file of a [`LocalIndex`], the
+    /// JSON Lines of [`Index::export_jsonl`] and [`Index::import_jsonl`] or a credentials file.
+    ///
+    /// [`LocalIndex`]: crate::LocalIndex
+    /// [`Index::export_jsonl`]: crate::Index::export_jsonl
+    /// [`Index::import_jsonl`]: crate::Index::import_jsonl
+    #[error("IO Error: {0}")]
+    IoError(std::io::Error),
+
+    /// An error encoding or decoding JSON written to or read from a file or stream, such as the
+    /// file of a [`LocalIndex`] or the JSON Lines of [`Index::export_jsonl`].
+    ///
+    /// [`LocalIndex`]: crate::LocalIndex
+    /// [`Index::export_jsonl`]: crate::Index::export_jsonl
+    #[error("Json Error: {0}")]
+    JsonError(serde_json::Error),
+
+    /// A gRPC connection could not be configured for the data plane url.
+    #[cfg(all(feature = "grpc", not(target_arch = "wasm32")))]
+    #[error("gRPC transport error: {0}")]
+    GrpcTransportError(tonic::transport::Error),
+
+    /// The api key couldn't be provided, for example because its environment variable isn't set
+    /// or a profile is missing from the credentials file.
+    #[error("Credentials error: {0}")]
+    CredentialsError(String),
+
+    /// A request was stopped by a [`Middleware`], which can also return any other error.
+    ///
+    /// [`Middleware`]: crate::middleware::Middleware
+    #[error("Request stopped by middleware: {0}")]
+    MiddlewareError(String),
+
     /// An error used for when the url value within an IndexDescription can't be found.
-    #[error("URL is not available within [`pine_client::http::models::DescribeStatus`]")]
+    #[error("The data plane url is not available, the index description has no host")]
     URLNotAvailable,
 
+    /// An index failed to initialize while waiting for it to become ready, its state is
+    /// [`DescribeStatusState::InitializationFailed`].
+    ///
+    /// [`DescribeStatusState::InitializationFailed`]: crate::models::DescribeStatusState::InitializationFailed
+    #[error("Index {name} failed to initialize")]
+    InitializationFailed {
+        /// The index name.
+        name: String,
+    },
+
+    /// An index or collection did not reach the state being waited for in time.
+    #[error("Timed out after {waited:?} waiting on {name}, last state was {state}")]
+    WaitTimeout {
+        /// The index or collection name.
+        name: String,
+        /// The last state seen.
+        state: String,
+        /// How long was waited.
+        waited: Duration,
+    },
+
     /// This is an internal error used for internal checks. This **should** never actually happen.
     #[error("Unsupported method: {}", method.as_str())]
     UnsupportedMethod {
@@ -62,3 +142,158 @@ pub enum Error {
         method: Method,
     },
 }
+
+impl Error {
+
+    /// The http status code of the response that caused the error, if there was a response. gRPC
+    /// statuses are mapped to their http equivalent.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Error::ReqwestResponseError(code, _) => Some(*code),
+            _ => self.response().map(|response| response.status)
+        }
+    }
+
+    /// The details of the error response sent by Pinecone, for every variant holding a
+    /// [`ResponseError`].
+    pub fn response(&self) -> Option<&ResponseError> {
+        match self {
+            Error::NotFound(response)
+            | Error::AlreadyExists(response)
+            | Error::Unauthorized(response)
+            | Error::RateLimited(response)
+            | Error::QuotaExceeded(response)
+            | Error::InvalidRequest(response)
+            | Error::ServerError(response)
+            | Error::PineconeResponseError(response) => Some(response),
+            _ => None
+        }
+    }
+
+    /// How long Pinecone asked to wait before sending the request again, if it did.
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.response().and_then(|response| response.retry_after)
+    }
+
+    /// Whether sending the same request again may succeed. This is true for rate limits, server
+    /// errors, timeouts and failed connections, but not for errors caused by the request itself
+    /// or by exceeded quotas.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::RateLimited(_) | Error::ServerError(_) => true,
+            #[cfg(not(target_arch = "wasm32"))]
+            Error::ReqwestError(err) if err.is_connect() => true,
+            Error::ReqwestError(err) => err.is_timeout() || err.is_request(),
+            _ => false
+        }
+    }
+}
+
+/// An error response sent by Pinecone, alongside the request that caused it. Failed gRPC calls are
+/// described by the http equivalent of their status, with the RPC as their path.
+#[derive(Debug, Clone)]
+pub struct ResponseError {
+    /// The status of the response.
+    pub status: StatusCode,
+    /// The method of the request.
+    pub method: Method,
+    /// The path of the request, without its query, or the RPC of a gRPC call such as
+    /// `/VectorService/Query`.
+    pub path: String,
+    /// The index the request was sent for, if it was sent through an [`Index`].
+    ///
+    /// [`Index`]: crate::Index
+    pub index: Option<String>,
+    /// The error message, taken from the response body.
+    pub message: String,
+    /// The body of the response, when it is a [`PineconeErrorResponse`].
+    pub response: Option<PineconeErrorResponse>,
+    /// The delay given by the `Retry-After` header of the response, if it has one.
+    pub retry_after: Option<Duration>
+}
+
+impl ResponseError {
+
+    /// Creates the error of a response from its body, which is either a [`PineconeErrorResponse`],
+    /// a json `{"error": {"message": ...}}` object as sent by the global control plane or text.
+    pub(crate) fn new(status: StatusCode, method: Method, path: String, index: Option<String>, body: String, retry_after: Option<Duration>) -> ResponseError {
+        let response = serde_json::from_str::<PineconeErrorResponse>(&body).ok();
+        let message = match response {
+            Some(ref response) => response.message.clone(),
+            None => serde_json::from_str::<serde_json::Value>(&body).ok()
+                .and_then(|value| value["error"]["message"].as_str().map(String::from))
+                .unwrap_or(body)
+        };
+        ResponseError{status, method, path, index, message, response, retry_after}
+    }
+
+    /// Classifies the error by its status, and by its message to tell exceeded quotas apart.
+    pub(crate) fn into_error(self) -> Error {
+        let response = Box::new(self);
+        match response.status {
+            StatusCode::PAYMENT_REQUIRED => Error::QuotaExceeded(response),
+            StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS if response.mentions_quota() => Error::QuotaExceeded(response),
+            StatusCode::NOT_FOUND => Error::NotFound(response),
+            StatusCode::CONFLICT => Error::AlreadyExists(response),
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Error::Unauthorized(response),
+            StatusCode::TOO_MANY_REQUESTS => Error::RateLimited(response),
+            StatusCode::BAD_REQUEST | StatusCode::PRECONDITION_FAILED | StatusCode::PAYLOAD_TOO_LARGE | StatusCode::UNPROCESSABLE_ENTITY => Error::InvalidRequest(response),
+            status if status.is_server_error() => Error::ServerError(response),
+            _ => Error::PineconeResponseError(response)
+        }
+    }
+
+    fn mentions_quota(&self) -> bool {
+        let message = self.message.to_lowercase();
+        ["quota", "reached the max", "monthly", "usage limit"].iter().any(|hint| message.contains(hint))
+    }
+}
+
+impl fmt::Display for ResponseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} ", self.method, self.path)?;
+        if let Some(ref index) = self.index {
+            write!(f, "on index {} ", index)?;
+        }
+        write!(f, "failed with status {}: {}", self.status, self.message)
+    }
+}
+
+#[cfg(test)]
+mod error_test {
+
+    use super::*;
+
+    fn classify(status: StatusCode, body: &str) -> Error {
+        ResponseError::new(status, Method::GET, "/databases/a".to_string(), None, body.to_string(), Some(Duration::from_secs(2))).into_error()
+    }
+
+    #[test]
+    fn test_classification() {
+        assert!(matches!(classify(StatusCode::NOT_FOUND, ""), Error::NotFound(_)));
+        assert!(matches!(classify(StatusCode::CONFLICT, ""), Error::AlreadyExists(_)));
+        assert!(matches!(classify(StatusCode::FORBIDDEN, "Invalid API Key"), Error::Unauthorized(_)));
+        assert!(matches!(classify(StatusCode::FORBIDDEN, "You've reached the max pods allowed in project"), Error::QuotaExceeded(_)));
+        assert!(matches!(classify(StatusCode::TOO_MANY_REQUESTS, "Monthly read unit limit exceeded"), Error::QuotaExceeded(_)));
+        assert!(matches!(classify(StatusCode::UNPROCESSABLE_ENTITY, ""), Error::InvalidRequest(_)));
+        assert!(matches!(classify(StatusCode::IM_A_TEAPOT, ""), Error::PineconeResponseError(_)));
+
+        let limited = classify(StatusCode::TOO_MANY_REQUESTS, "Too many requests");
+        assert!(matches!(limited, Error::RateLimited(_)));
+        assert_eq!(limited.retry_after(), Some(Duration::from_secs(2)));
+        assert!(limited.is_retryable());
+        assert!(classify(StatusCode::BAD_GATEWAY, "").is_retryable());
+        assert!(!classify(StatusCode::BAD_REQUEST, "").is_retryable());
+    }
+
+    #[test]
+    fn test_messages() {
+        let legacy = classify(StatusCode::BAD_REQUEST, r#"{"code":3,"message":"bad vector","details":[]}"#);
+        assert_eq!(legacy.response().unwrap().message, "bad vector");
+        assert_eq!(legacy.response().unwrap().response.as_ref().unwrap().code, 3);
+        let global = classify(StatusCode::NOT_FOUND, r#"{"error":{"code":"NOT_FOUND","message":"Resource a not found"},"status":404}"#);
+        assert_eq!(global.response().unwrap().message, "Resource a not found");
+        assert_eq!(global.to_string(), "Not found, GET /databases/a failed with status 404 Not Found: Resource a not found");
+        assert_eq!(global.status(), Some(StatusCode::NOT_FOUND));
+    }
+}
@@ -0,0 +1,290 @@
+//! Conversions between the [`models`] and the protobuf messages of the gRPC data plane, in both
+//! directions so that the messages can be both sent and served.
+
+use prost_types::{value::Kind, ListValue, NullValue, Struct};
+use serde_json::{Number, Value};
+
+use crate::models::{self, Filter, MappedValue, Namespace};
+use super::proto;
+
+pub(crate) fn to_struct(map: MappedValue) -> Struct {
+    Struct {
+        fields: map.into_iter().map(|(key, value)| (key, to_proto_value(value))).collect()
+    }
+}
+
+fn to_proto_value(value: Value) -> prost_types::Value {
+    let kind = match value {
+        Value::Null => Kind::NullValue(NullValue::NullValue as i32),
+        Value::Bool(b) => Kind::BoolValue(b),
+        Value::Number(n) => Kind::NumberValue(n.as_f64().unwrap_or_default()),
+        Value::String(s) => Kind::StringValue(s),
+        Value::Array(values) => Kind::ListValue(ListValue{values: values.into_iter().map(to_proto_value).collect()}),
+        Value::Object(map) => Kind::StructValue(to_struct(map.into_iter().collect()))
+    };
+    prost_types::Value{kind: Some(kind)}
+}
+
+pub(crate) fn from_struct(value: Struct) -> MappedValue {
+    value.fields.into_iter().map(|(key, value)| (key, from_proto_value(value))).collect()
+}
+
+/// Protobuf only has doubles, whole numbers are turned back into integers.
+fn from_proto_value(value: prost_types::Value) -> Value {
+    match value.kind {
+        None | Some(Kind::NullValue(_)) => Value::Null,
+        Some(Kind::BoolValue(b)) => Value::Bool(b),
+        Some(Kind::NumberValue(n)) if n.fract() == 0.0 && n.abs() < 2f64.powi(53) => Value::from(n as i64),
+        Some(Kind::NumberValue(n)) => Number::from_f64(n).map_or(Value::Null, Value::Number),
+        Some(Kind::StringValue(s)) => Value::String(s),
+        Some(Kind::ListValue(list)) => Value::Array(list.values.into_iter().map(from_proto_value).collect()),
+        Some(Kind::StructValue(s)) => Value::Object(from_struct(s).into_iter().collect())
+    }
+}
+
+pub(crate) fn filter_to_struct(filter: Filter) -> Struct {
+    match filter.to_value() {
+        Value::Object(map) => to_struct(map.into_iter().collect()),
+        _ => Struct::default()
+    }
+}
+
+fn non_empty(value: String) -> Option<String> {
+    (!value.is_empty()).then_some(value)
+}
+
+impl From<models::SparseValues> for proto::SparseValues {
+    fn from(value: models::SparseValues) -> Self {
+        proto::SparseValues {
+            indices: value.indeces,
+            values: value.values
+        }
+    }
+}
+
+impl From<proto::SparseValues> for models::SparseValues {
+    fn from(value: proto::SparseValues) -> Self {
+        models::SparseValues {
+            indeces: value.indices,
+            values: value.values
+        }
+    }
+}
+
+impl From<models::Vector> for proto::Vector {
+    fn from(value: models::Vector) -> Self {
+        proto::Vector {
+            id: value.id,
+            values: value.values,
+            sparse_values: value.sparse_values.map(Into::into),
+            metadata: value.metadata.map(to_struct)
+        }
+    }
+}
+
+impl From<proto::Vector> for models::Vector {
+    fn from(value: proto::Vector) -> Self {
+        models::Vector {
+            id: value.id,
+            values: value.values,
+            sparse_values: value.sparse_values.map(Into::into),
+            metadata: value.metadata.map(from_struct)
+        }
+    }
+}
+
+impl From<models::UpsertResponse> for proto::UpsertResponse {
+    fn from(value: models::UpsertResponse) -> Self {
+        proto::UpsertResponse {
+            upserted_count: value.upserted_count as u32
+        }
+    }
+}
+
+impl From<proto::UpsertResponse> for models::UpsertResponse {
+    fn from(value: proto::UpsertResponse) -> Self {
+        models::UpsertResponse {
+            upserted_count: value.upserted_count as usize
+        }
+    }
+}
+
+impl From<models::QueryRequest> for proto::QueryRequest {
+    fn from(value: models::QueryRequest) -> Self {
+        proto::QueryRequest {
+            namespace: value.namespace.unwrap_or_default(),
+            top_k: value.top_k as u32,
+            filter: value.filter.map(filter_to_struct),
+            include_values: value.include_values,
+            include_metadata: value.include_metadata,
+            vector: value.vector.unwrap_or_default(),
+            sparse_vector: value.sparse_vector.map(Into::into),
+            id: value.id.unwrap_or_default()
+        }
+    }
+}
+
+impl From<proto::QueryRequest> for models::QueryRequest {
+    fn from(value: proto::QueryRequest) -> Self {
+        models::QueryRequest {
+            namespace: non_empty(value.namespace),
+            top_k: value.top_k as usize,
+            filter: value.filter.map(|f| Filter::Raw(from_struct(f))),
+            include_values: value.include_values,
+            include_metadata: value.include_metadata,
+            vector: (!value.vector.is_empty()).then_some(value.vector),
+            sparse_vector: value.sparse_vector.map(Into::into),
+            id: non_empty(value.id)
+        }
+    }
+}
+
+impl From<models::Match> for proto::ScoredVector {
+    fn from(value: models::Match) -> Self {
+        proto::ScoredVector {
+            id: value.id,
+            score: value.score.unwrap_or_default(),
+            values: value.values.unwrap_or_default(),
+            sparse_values: value.sparse_values.map(Into::into),
+            metadata: value.metadata.map(to_struct)
+        }
+    }
+}
+
+impl From<proto::ScoredVector> for models::Match {
+    fn from(value: proto::ScoredVector) -> Self {
+        models::Match {
+            id: value.id,
+            score: Some(value.score),
+            values: (!value.values.is_empty()).then_some(value.values),
+            sparse_values: value.sparse_values.map(Into::into),
+            metadata: value.metadata.map(from_struct)
+        }
+    }
+}
+
+impl From<models::QueryResponse> for proto::QueryResponse {
+    fn from(value: models::QueryResponse) -> Self {
+        proto::QueryResponse {
+            matches: value.matches.into_iter().map(Into::into).collect(),
+            namespace: value.namespace
+        }
+    }
+}
+
+impl From<proto::QueryResponse> for models::QueryResponse {
+    fn from(value: proto::QueryResponse) -> Self {
+        models::QueryResponse {
+            matches: value.matches.into_iter().map(Into::into).collect(),
+            namespace: value.namespace
+        }
+    }
+}
+
+impl From<models::FetchRequest> for proto::FetchRequest {
+    fn from(value: models::FetchRequest) -> Self {
+        proto::FetchRequest {
+            ids: value.ids,
+            namespace: value.namespace.unwrap_or_default()
+        }
+    }
+}
+
+impl From<proto::FetchRequest> for models::FetchRequest {
+    fn from(value: proto::FetchRequest) -> Self {
+        models::FetchRequest {
+            ids: value.ids,
+            namespace: non_empty(value.namespace)
+        }
+    }
+}
+
+impl From<models::FetchResponse> for proto::FetchResponse {
+    fn from(value: models::FetchResponse) -> Self {
+        proto::FetchResponse {
+            vectors: value.vectors.into_iter().map(|(id, v)| (id, v.into())).collect(),
+            namespace: value.namespace
+        }
+    }
+}
+
+impl From<proto::FetchResponse> for models::FetchResponse {
+    fn from(value: proto::FetchResponse) -> Self {
+        models::FetchResponse {
+            vectors: value.vectors.into_iter().map(|(id, v)| (id, v.into())).collect(),
+            namespace: value.namespace
+        }
+    }
+}
+
+impl From<models::UpdateRequest> for proto::UpdateRequest {
+    fn from(value: models::UpdateRequest) -> Self {
+        proto::UpdateRequest {
+            id: value.id,
+            values: value.values.unwrap_or_default(),
+            sparse_values: value.sparse_values.map(Into::into),
+            set_metadata: value.metadata.map(to_struct),
+            namespace: value.namespace.unwrap_or_default()
+        }
+    }
+}
+
+impl From<proto::UpdateRequest> for models::UpdateRequest {
+    fn from(value: proto::UpdateRequest) -> Self {
+        models::UpdateRequest {
+            id: value.id,
+            values: (!value.values.is_empty()).then_some(value.values),
+            sparse_values: value.sparse_values.map(Into::into),
+            metadata: value.set_metadata.map(from_struct),
+            namespace: non_empty(value.namespace)
+        }
+    }
+}
+
+impl From<models::DeleteRequest> for proto::DeleteRequest {
+    fn from(value: models::DeleteRequest) -> Self {
+        proto::DeleteRequest {
+            ids: value.ids.unwrap_or_default(),
+            delete_all: value.delete_all,
+            namespace: value.namespace.unwrap_or_default(),
+            filter: value.filter.map(filter_to_struct)
+        }
+    }
+}
+
+impl From<proto::DeleteRequest> for models::DeleteRequest {
+    fn from(value: proto::DeleteRequest) -> Self {
+        models::DeleteRequest {
+            ids: (!value.ids.is_empty()).then_some(value.ids),
+            delete_all: value.delete_all,
+            namespace: non_empty(value.namespace),
+            filter: value.filter.map(|f| Filter::Raw(from_struct(f)))
+        }
+    }
+}
+
+impl From<models::IndexStats> for proto::DescribeIndexStatsResponse {
+    fn from(value: models::IndexStats) -> Self {
+        proto::DescribeIndexStatsResponse {
+            namespaces: value.namespaces.into_iter()
+                .map(|(name, n)| (name, proto::NamespaceSummary{vector_count: n.vector_count as u32}))
+                .collect(),
+            dimension: value.dimension as u32,
+            index_fullness: value.index_fullness as f32,
+            total_vector_count: value.total_vector_count
+        }
+    }
+}
+
+impl From<proto::DescribeIndexStatsResponse> for models::IndexStats {
+    fn from(value: proto::DescribeIndexStatsResponse) -> Self {
+        models::IndexStats {
+            namespaces: value.namespaces.into_iter()
+                .map(|(name, n)| (name, Namespace{vector_count: n.vector_count as usize}))
+                .collect(),
+            dimension: value.dimension as usize,
+            index_fullness: value.index_fullness as u32,
+            total_vector_count: value.total_vector_count
+        }
+    }
+}
@@ -0,0 +1,458 @@
+//! The gRPC transport of the data plane, enabled by the `grpc` feature.
+
+use std::future::Future;
+
+use reqwest::{Method, StatusCode};
+use serde_json::Value;
+use tonic::{
+    Code,
+    Status,
+    metadata::{Ascii, MetadataValue},
+    transport::{Channel, ClientTlsConfig, Endpoint}
+};
+
+use crate::{
+    Error,
+    Index,
+    Result,
+    RetryPolicy,
+    error::ResponseError,
+    models::{
+        DeleteRequest,
+        FetchRequest,
+        FetchResponse,
+        Filter,
+        IndexStats,
+        QueryRequest,
+        QueryResponse,
+        UpdateRequest,
+        UpsertResponse,
+        Vector
+    },
+    rest::{retry, Connection, Credentials}
+};
+
+mod convert;
+
+#[allow(missing_docs, dead_code, clippy::all)]
+mod proto {
+    tonic::include_proto!("_");
+}
+
+use proto::vector_service_client::VectorServiceClient;
+
+/// A connection to the data plane of an index over gRPC, created with [`Index::grpc`].
+///
+/// A [`GrpcIndex`] offers the same data plane operations as [`Index`], taking and returning the
+/// same [`models`](crate::models), but sends them as protobuf messages over a single HTTP/2
+/// connection which is cheaper for large upserts and many small queries. Requests are retried with
+/// the [`RetryPolicy`] of the [`Client`], gRPC status codes being matched against
+/// [`RetryPolicy::retry_statuses`] through their http equivalent. Failed calls return the same
+/// [`Error`] variants as [`Index`], classified by that http equivalent, with the RPC, such as
+/// `/VectorService/Query`, as the path of their [`ResponseError`]. Clones share the connection.
+///
+/// ```no_run
+/// use pinenut::{Client, models::QueryRequest};
+///
+/// # async fn run(client: Client) -> pinenut::Result<()> {
+/// let index = client.index("my-index").grpc().await?;
+/// let query = QueryRequest{vector: Some(vec![0.5; 32]), top_k: 3, ..Default::default()};
+/// let response = index.query(query).await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Client`]: crate::Client
+#[derive(Clone)]
+pub struct GrpcIndex {
+    name: String,
+    client: VectorServiceClient<Channel>,
+    creds: Credentials,
+    retry: RetryPolicy
+}
+
+impl Index {
+
+    /// Opens a gRPC connection to the data plane of the index, describing the index first if its
+    /// url isn't known yet. The connection itself is established on the first request.
+    ///
+    /// # Error
+    ///
+    /// Returns an [`Error::GrpcTransportError`] if the data plane url or user agent can't be used
+    /// by the connection.
+    pub async fn grpc(&self) -> Result<GrpcIndex> {
+        let url = self.resolve_url().await?;
+        GrpcIndex::connect(self, self.name(), url)
+    }
+}
+
+impl GrpcIndex {
+
+    fn connect<C: Connection>(con: &C, name: impl Into<String>, url: String) -> Result<GrpcIndex> {
+        let config = con.config();
+        let mut endpoint = Endpoint::from_shared(url).map_err(Error::GrpcTransportError)?;
+        if endpoint.uri().scheme_str() == Some("https") {
+            endpoint = endpoint.tls_config(ClientTlsConfig::new()).map_err(Error::GrpcTransportError)?;
+        }
+        if let Some(timeout) = config.timeout {
+            endpoint = endpoint.timeout(timeout);
+        }
+        if let Some(ref user_agent) = config.user_agent {
+            endpoint = endpoint.user_agent(user_agent.as_str()).map_err(Error::GrpcTransportError)?;
+        }
+        Ok(GrpcIndex {
+            name: name.into(),
+            client: VectorServiceClient::new(endpoint.connect_lazy()),
+            creds: con.credentials().clone(),
+            retry: config.retry.clone()
+        })
+    }
+
+    /// The name of the index.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The api key of the next request as metadata, requested for every request so that rotated
+    /// keys are picked up.
+    fn api_key(&self) -> Result<MetadataValue<Ascii>> {
+        MetadataValue::try_from(self.creds.api_key()?.expose()).map_err(|_| Error::ArgumentError {
+            name: "api_key".to_string(),
+            found: "a key with characters not allowed in a header".to_string(),
+            expected: "a visible ascii key".to_string()
+        })
+    }
+
+    /// Sends a message to the RPC named `name` with the api key, retrying it according to the
+    /// [`RetryPolicy`].
+    async fn call<M, R, F, Fut>(&self, name: &str, message: M, rpc: F) -> Result<R>
+    where
+        M: Clone,
+        F: Fn(VectorServiceClient<Channel>, tonic::Request<M>) -> Fut,
+        Fut: Future<Output = std::result::Result<tonic::Response<R>, Status>>
+    {
+        let mut attempt = 1;
+        loop {
+            let mut request = tonic::Request::new(message.clone());
+            request.metadata_mut().insert("api-key", self.api_key()?);
+            match rpc(self.client.clone(), request).await {
+                Ok(response) => return Ok(response.into_inner()),
+                Err(status) if attempt < self.retry.max_attempts && self.retry.retries_status(http_status(status.code()), true) => {
+                    retry::sleep(self.retry.delay(attempt, None)).await;
+                    attempt += 1;
+                },
+                Err(status) => return Err(self.status_error(name, status))
+            }
+        }
+    }
+
+    /// Turns the status a call failed with into the [`Error`] an http request failing with its
+    /// http equivalent returns.
+    fn status_error(&self, name: &str, status: Status) -> Error {
+        ResponseError {
+            status: http_status(status.code()),
+            method: Method::POST,
+            path: format!("/VectorService/{}", name),
+            index: Some(self.name.clone()),
+            message: status.message().to_string(),
+            response: None,
+            retry_after: None
+        }.into_error()
+    }
+
+    /// Inserts or replaces `vectors` within `namespace`, see [`Index::upsert`].
+    pub async fn upsert(&self, namespace: String, vectors: Vec<Vector>) -> Result<UpsertResponse> {
+        let request = proto::UpsertRequest {
+            vectors: vectors.into_iter().map(Into::into).collect(),
+            namespace
+        };
+        let response = self.call("Upsert", request, |mut c, r| async move { c.upsert(r).await }).await?;
+        Ok(response.into())
+    }
+
+    /// Searches a namespace using a query vector or id, see [`Index::query`].
+    pub async fn query(&self, request: QueryRequest) -> Result<QueryResponse> {
+        let request = proto::QueryRequest::from(request);
+        let response = self.call("Query", request, |mut c, r| async move { c.query(r).await }).await?;
+        Ok(response.into())
+    }
+
+    /// Looks up vectors of a namespace by id, see [`Index::fetch`].
+    pub async fn fetch(&self, request: FetchRequest) -> Result<FetchResponse> {
+        let request = proto::FetchRequest::from(request);
+        let response = self.call("Fetch", request, |mut c, r| async move { c.fetch(r).await }).await?;
+        Ok(response.into())
+    }
+
+    /// Updates a vector within the index, see [`Index::update`]. The Ok() value is an empty json
+    /// object and should be ignored.
+    pub async fn update(&self, request: UpdateRequest) -> Result<Value> {
+        let request = proto::UpdateRequest::from(request);
+        self.call("Update", request, |mut c, r| async move { c.update(r).await }).await?;
+        Ok(serde_json::json!({}))
+    }
+
+    /// Deletes vectors by id, by metadata filter or every vector of a namespace, see
+    /// [`Index::delete_vectors`].
+    ///
+    /// # Error
+    ///
+    /// Returns an [`Error::ArgumentError`] without making a request if the [`DeleteRequest`]
+    /// doesn't target exactly one of ids, delete_all or filter.
+    pub async fn delete_vectors(&self, request: DeleteRequest) -> Result<Value> {
+        request.validate()?;
+        let request = proto::DeleteRequest::from(request);
+        self.call("Delete", request, |mut c, r| async move { c.delete(r).await }).await?;
+        Ok(serde_json::json!({}))
+    }
+
+    /// Requests the latest [`IndexStats`].
+    pub async fn describe_stats(&self) -> Result<IndexStats> {
+        let request = proto::DescribeIndexStatsRequest::default();
+        let response = self.call("DescribeIndexStats", request, |mut c, r| async move { c.describe_index_stats(r).await }).await?;
+        Ok(response.into())
+    }
+
+    /// Requests the [`IndexStats`] of only the vectors matching `filter`, see
+    /// [`Index::describe_stats_filtered`].
+    pub async fn describe_stats_filtered(&self, filter: Filter) -> Result<IndexStats> {
+        let request = proto::DescribeIndexStatsRequest{filter: Some(convert::filter_to_struct(filter))};
+        let response = self.call("DescribeIndexStats", request, |mut c, r| async move { c.describe_index_stats(r).await }).await?;
+        Ok(response.into())
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::VectorStore for GrpcIndex {
+
+    async fn upsert(&self, namespace: String, vectors: Vec<Vector>) -> Result<UpsertResponse> {
+        GrpcIndex::upsert(self, namespace, vectors).await
+    }
+
+    async fn query(&self, request: QueryRequest) -> Result<QueryResponse> {
+        GrpcIndex::query(self, request).await
+    }
+
+    async fn fetch(&self, request: FetchRequest) -> Result<FetchResponse> {
+        GrpcIndex::fetch(self, request).await
+    }
+
+    async fn update(&self, request: UpdateRequest) -> Result<Value> {
+        GrpcIndex::update(self, request).await
+    }
+
+    async fn delete_vectors(&self, request: DeleteRequest) -> Result<Value> {
+        GrpcIndex::delete_vectors(self, request).await
+    }
+
+    async fn describe_stats(&self) -> Result<IndexStats> {
+        GrpcIndex::describe_stats(self).await
+    }
+}
+
+/// The http status equivalent to a gRPC status code, used to apply the [`RetryPolicy`] and to
+/// classify the [`Error`] of a failed call.
+fn http_status(code: Code) -> StatusCode {
+    match code {
+        Code::Ok => StatusCode::OK,
+        Code::InvalidArgument | Code::OutOfRange | Code::FailedPrecondition => StatusCode::BAD_REQUEST,
+        Code::Unauthenticated => StatusCode::UNAUTHORIZED,
+        Code::PermissionDenied => StatusCode::FORBIDDEN,
+        Code::NotFound => StatusCode::NOT_FOUND,
+        Code::AlreadyExists | Code::Aborted => StatusCode::CONFLICT,
+        Code::ResourceExhausted => StatusCode::TOO_MANY_REQUESTS,
+        Code::Unimplemented => StatusCode::NOT_IMPLEMENTED,
+        Code::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+        Code::DeadlineExceeded => StatusCode::GATEWAY_TIMEOUT,
+        Code::Cancelled | Code::Unknown | Code::Internal | Code::DataLoss => StatusCode::INTERNAL_SERVER_ERROR
+    }
+}
+
+#[cfg(test)]
+mod grpc_test {
+
+    use super::*;
+    use std::{sync::{Arc, Mutex, atomic::{AtomicUsize, Ordering}}, time::Duration};
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::{Request, Response, transport::Server};
+    use crate::{
+        Client,
+        mock::{MockPinecone, MOCK_API_KEY},
+        models::Metric,
+        rest::memory::{vector, MemoryIndex}
+    };
+    use super::proto::vector_service_server::{VectorService, VectorServiceServer};
+
+    /// A tonic server standing in for the data plane of an index, failing its first calls with
+    /// `Unavailable` when asked to.
+    struct StandIn {
+        index: Mutex<MemoryIndex>,
+        failures: AtomicUsize,
+        calls: AtomicUsize
+    }
+
+    impl StandIn {
+        #[allow(clippy::result_large_err)]
+        fn check<T>(&self, request: &Request<T>) -> std::result::Result<(), Status> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if request.metadata().get("api-key").is_none_or(|key| key != MOCK_API_KEY) {
+                return Err(Status::unauthenticated("invalid api key"));
+            }
+            if self.failures.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |f| f.checked_sub(1)).is_ok() {
+                return Err(Status::unavailable("try again"));
+            }
+            Ok(())
+        }
+    }
+
+    fn invalid(err: Error) -> Status {
+        Status::invalid_argument(err.to_string())
+    }
+
+    #[tonic::async_trait]
+    impl VectorService for StandIn {
+        async fn upsert(&self, request: Request<proto::UpsertRequest>) -> std::result::Result<Response<proto::UpsertResponse>, Status> {
+            self.check(&request)?;
+            let request = request.into_inner();
+            let vectors = request.vectors.into_iter().map(Into::into).collect();
+            let response = self.index.lock().unwrap().upsert(&request.namespace, vectors).map_err(invalid)?;
+            Ok(Response::new(response.into()))
+        }
+
+        async fn delete(&self, request: Request<proto::DeleteRequest>) -> std::result::Result<Response<proto::DeleteResponse>, Status> {
+            self.check(&request)?;
+            self.index.lock().unwrap().delete(&request.into_inner().into()).map_err(invalid)?;
+            Ok(Response::new(proto::DeleteResponse{}))
+        }
+
+        async fn fetch(&self, request: Request<proto::FetchRequest>) -> std::result::Result<Response<proto::FetchResponse>, Status> {
+            self.check(&request)?;
+            let request = request.into_inner();
+            Ok(Response::new(self.index.lock().unwrap().fetch(&request.ids, &request.namespace).into()))
+        }
+
+        async fn query(&self, request: Request<proto::QueryRequest>) -> std::result::Result<Response<proto::QueryResponse>, Status> {
+            self.check(&request)?;
+            let response = self.index.lock().unwrap().query(&request.into_inner().into()).map_err(invalid)?;
+            Ok(Response::new(response.into()))
+        }
+
+        async fn update(&self, request: Request<proto::UpdateRequest>) -> std::result::Result<Response<proto::UpdateResponse>, Status> {
+            self.check(&request)?;
+            match self.index.lock().unwrap().update(request.into_inner().into()).map_err(invalid)? {
+                true => Ok(Response::new(proto::UpdateResponse{})),
+                false => Err(Status::not_found("vector not found"))
+            }
+        }
+
+        async fn describe_index_stats(&self, request: Request<proto::DescribeIndexStatsRequest>) -> std::result::Result<Response<proto::DescribeIndexStatsResponse>, Status> {
+            self.check(&request)?;
+            let filter = request.into_inner().filter.map(|f| Filter::Raw(convert::from_struct(f)));
+            Ok(Response::new(self.index.lock().unwrap().filtered_stats(filter.as_ref()).into()))
+        }
+    }
+
+    async fn start(failures: usize) -> (Arc<StandIn>, String) {
+        let stand_in = Arc::new(StandIn {
+            index: Mutex::new(MemoryIndex::new(3, Metric::COSINE)),
+            failures: AtomicUsize::new(failures),
+            calls: AtomicUsize::new(0)
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = Server::builder()
+            .add_service(VectorServiceServer::from_arc(stand_in.clone()))
+            .serve_with_incoming(TcpListenerStream::new(listener));
+        tokio::spawn(server);
+        (stand_in, url)
+    }
+
+    async fn grpc_index(client: &Client, url: &str) -> GrpcIndex {
+        client.index_by_host("grpc", url).grpc().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_operations() {
+        let mock = MockPinecone::start().await;
+        let client = mock.client().await.unwrap();
+        let (_stand_in, url) = start(0).await;
+        let index = grpc_index(&client, &url).await;
+
+        let upserted = index.upsert("ns".to_string(), vec![
+            vector("a", vec![1.0, 0.0, 0.0], serde_json::json!({"genre": "drama", "year": 2019, "tags": ["x", "y"]})),
+            vector("b", vec![0.0, 1.0, 0.0], serde_json::json!({"genre": "comedy", "rating": 4.5}))
+        ]).await.unwrap();
+        assert_eq!(upserted.upserted_count, 2);
+
+        let query = QueryRequest{namespace: Some("ns".to_string()), vector: Some(vec![0.9, 0.1, 0.0]), top_k: 2, include_metadata: true, ..Default::default()};
+        let response = index.query(query.clone()).await.unwrap();
+        assert_eq!(response.matches.iter().map(|m| m.id.as_str()).collect::<Vec<_>>(), vec!["a", "b"]);
+        assert_eq!(response.matches[0].metadata.as_ref().unwrap()["year"], serde_json::json!(2019));
+        assert_eq!(response.matches[1].metadata.as_ref().unwrap()["rating"], serde_json::json!(4.5));
+        let filtered = index.query(QueryRequest{filter: Some(Filter::eq("genre", "comedy")), ..query}).await.unwrap();
+        assert_eq!(filtered.matches.len(), 1);
+        assert_eq!(filtered.matches[0].id, "b");
+
+        index.update(UpdateRequest{id: "a".to_string(), values: Some(vec![0.0, 0.0, 1.0]), namespace: Some("ns".to_string()), ..Default::default()}).await.unwrap();
+        let fetched = index.fetch(FetchRequest{ids: vec!["a".to_string(), "z".to_string()], namespace: Some("ns".to_string())}).await.unwrap();
+        assert_eq!(fetched.namespace, "ns");
+        assert_eq!(fetched.vectors.len(), 1);
+        assert_eq!(fetched.vectors["a"].values, vec![0.0, 0.0, 1.0]);
+        assert_eq!(fetched.vectors["a"].metadata.as_ref().unwrap()["tags"], serde_json::json!(["x", "y"]));
+
+        index.delete_vectors(DeleteRequest{ids: Some(vec!["b".to_string()]), namespace: Some("ns".to_string()), ..Default::default()}).await.unwrap();
+        let stats = index.describe_stats().await.unwrap();
+        assert_eq!((stats.dimension, stats.total_vector_count, stats.namespaces["ns"].vector_count), (3, 1, 1));
+        assert_eq!(index.describe_stats_filtered(Filter::eq("genre", "comedy")).await.unwrap().total_vector_count, 0);
+        assert_eq!(index.describe_stats_filtered(Filter::gte("year", 2019)).await.unwrap().total_vector_count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_errors() {
+        let mock = MockPinecone::start().await;
+        let client = mock.client().await.unwrap();
+        let (stand_in, url) = start(0).await;
+        let index = grpc_index(&client, &url).await;
+
+        match index.update(UpdateRequest{id: "missing".to_string(), values: Some(vec![1.0, 0.0, 0.0]), ..Default::default()}).await {
+            Err(Error::NotFound(err)) => {
+                assert_eq!((err.status, err.method, err.path.as_str()), (StatusCode::NOT_FOUND, Method::POST, "/VectorService/Update"));
+                assert_eq!(err.index.as_deref(), Some("grpc"));
+                assert_eq!(err.message, "vector not found");
+            },
+            other => panic!("Expected a not found status: {:?}", other)
+        }
+        let invalid = DeleteRequest{delete_all: true, ids: Some(vec!["a".to_string()]), ..Default::default()};
+        assert!(matches!(index.delete_vectors(invalid).await, Err(Error::ArgumentError{..})));
+        assert_eq!(stand_in.calls.load(Ordering::SeqCst), 1, "invalid requests aren't sent");
+
+        let other = MockPinecone::with_api_key("other-key").await;
+        let unauthorized = grpc_index(&other.client().await.unwrap(), &url).await;
+        match unauthorized.describe_stats().await {
+            Err(Error::Unauthorized(err)) => assert_eq!(err.path, "/VectorService/DescribeIndexStats"),
+            other => panic!("Expected an unauthenticated status: {:?}", other.map(|s| s.total_vector_count))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retries_unavailable() {
+        let mock = MockPinecone::start().await;
+        let policy = RetryPolicy{max_attempts: 3, base_delay: Duration::from_millis(1), ..Default::default()};
+        let client = mock.client_builder().retry_policy(policy).build().await.unwrap();
+        let (stand_in, url) = start(2).await;
+        let index = grpc_index(&client, &url).await;
+        assert_eq!(index.describe_stats().await.unwrap().total_vector_count, 0);
+        assert_eq!(stand_in.calls.load(Ordering::SeqCst), 3);
+
+        let (stand_in, url) = start(5).await;
+        let client = mock.client_builder().retry_policy(RetryPolicy::none()).build().await.unwrap();
+        match grpc_index(&client, &url).await.describe_stats().await {
+            Err(err @ Error::ServerError(_)) => {
+                assert_eq!(err.status(), Some(StatusCode::SERVICE_UNAVAILABLE));
+                assert!(err.is_retryable());
+            },
+            other => panic!("Expected an unavailable status: {:?}", other.map(|s| s.total_vector_count))
+        }
+        assert_eq!(stand_in.calls.load(Ordering::SeqCst), 1);
+    }
+}
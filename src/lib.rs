@@ -4,8 +4,9 @@
 //! query, and update data within Pinecone as well more unmentioned commands. More details about
 //! the different api methods can be found [here](https://docs.pinecone.io/reference/describe_index_stats_post)
 //!
-//! This crate currently only supports the http / rest pinecone api and does not support GRCP. GRCP
-//! will be implemented into the future as opt in. Http is currently the default
+//! Http / rest is the default transport. Enabling the `grpc` feature adds `Index::grpc`, which
+//! sends the data plane operations of an index over gRPC instead, taking and returning the same
+//! [`models`]. The control plane is always reached over http.
 //!
 //! To connect, initalize a [`Client`] using the [`Client::new`] method. This is an asynchronous
 //! operation that will also validate you're credentials and will error if invalid credentials are
@@ -15,6 +16,36 @@
 //! get information about the index and subsequently validate the credentials if it goes through
 //! successfully.
 //!
+//! [`Client::new`] talks to the legacy, per environment, control plane. Projects using serverless
+//! indexes are served by the global control plane instead, which is selected through
+//! [`Client::builder`] with [`ControlPlane::Global`].
+//!
+//! [`LocalIndex`] offers the data plane operations of [`Index`] entirely in memory, optionally
+//! persisted to a file, for running without a Pinecone account. Both implement [`VectorStore`] so
+//! that application code can be written against either.
+//!
+//! Enabling the `mock` feature adds `mock::MockPinecone`, an in-process server emulating
+//! Pinecone so that code using this crate can be tested offline.
+//!
+//! Instead of an api key, [`Client::builder_with_credentials`] takes a [`CredentialsProvider`]
+//! such as the environment variables read by `credentials::EnvCredentials`, a named profile of
+//! `~/.config/pinenut` with the `profiles` feature, or keys that can be rotated while the client
+//! is in use, see the [`credentials`] module.
+//!
+//! Hooks can be run around every request, for example to add headers or log requests, by adding
+//! a [`Middleware`] to the [`ClientBuilder`], see the [`middleware`] module.
+//!
+//! Enabling the `tracing` feature wraps every http request in a `pinecone_request` span of the
+//! [tracing](https://docs.rs/tracing) crate, recording the operation, index, namespace, number of
+//! vectors, `top_k`, status code, latency and number of retries. Headers, and so the api key, are
+//! never recorded.
+//!
+//! Enabling the `cli` feature builds `pinenut`, a command line tool listing, describing, creating,
+//! configuring and deleting indexes and collections, e.g. `pinenut describe-stats my-index -o json`.
+//! It also queries, fetches and updates vectors and upserts them from JSON Lines or CSV files, e.g.
+//! `pinenut query my-index --id movie-1 --top-k 5 --filter '{"genre":"drama"}'`.
+//! It reads the api key from `PINECONE_API_KEY` or from a profile of `~/.config/pinenut`.
+//!
 //! Below is a basic client and index example.
 //!```no_run
 //!use pinenut::{Client, models::Vector};
@@ -22,9 +53,11 @@
 //!async fn index_upsert() {
 //!
 //!    // We create an instance of client first and firstmost. Panics if it couldn't authenticate.
-//!    let client = Client::new(env!("PINECONE_API_KEY"), env!("PINECONE_ENV")).await.unwrap();
+//!    let api_key = std::env::var("PINECONE_API_KEY").unwrap();
+//!    let environment = std::env::var("PINECONE_ENV").unwrap();
+//!    let client = Client::new(api_key, environment).await.unwrap();
 //!    // creates an index, will not authenticate.
-//!    let mut index = client.index(env!("PINECONE_INDEX_NAME"));
+//!    let index = client.index("my-index");
 //!
 //!    // We use describe as a form of authenticate, panicing if we couldn't authenticate.
 //!    let _ = index.describe().await.unwrap();
@@ -55,11 +88,23 @@ macro_rules! if_rest {
     )*}
 }
 
-//TODO: create macro for GRCP when that becomes implemented
+macro_rules! if_grpc {
+    ($($item:item)*) => {$(
+        #[cfg(all(feature="grpc", not(target_arch="wasm32")))]
+        $item
+    )*}
+}
 
 if_rest! {
     mod rest;
-    pub use self::rest::{models, Client, Index};
+    pub use self::rest::{batch, credentials, middleware, models, transfer, Client, ClientBuilder, ControlPlane, CredentialsProvider, Index, LocalIndex, Middleware, RetryPolicy, VectorStore, WaitOptions};
+    #[cfg(all(feature = "mock", not(target_arch = "wasm32")))]
+    pub use self::rest::mock;
+}
+
+if_grpc! {
+    mod grpc;
+    pub use self::grpc::GrpcIndex;
 }
 
 pub mod error;
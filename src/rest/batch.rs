@@ -0,0 +1,152 @@
+//! Types used to split large upserts into several requests, see [`Index::upsert_batched`].
+//!
+//! Pinecone limits both the number of vectors and the size of the payload sent in a single upsert
+//! request, so large uploads have to be broken up into chunks. Chunks are sent independently of
+//! eachother which means one can fail while the rest succeed, these failures are reported in
+//! [`BatchUpsertResponse::failures`].
+//!
+//! [`Index::upsert_batched`]: crate::Index::upsert_batched
+
+use crate::{Error, Result, models::{UpsertResponse, Vector}};
+
+/// The maximum number of vectors Pinecone accepts within a single upsert request.
+pub const MAX_VECTORS_PER_REQUEST: usize = 1000;
+
+/// The maximum size, in bytes, of a single upsert request accepted by Pinecone.
+pub const MAX_BYTES_PER_REQUEST: usize = 2 * 1024 * 1024;
+
+/// Controls how vectors are split into chunks and how many of them are sent at once.
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    /// Maximum number of vectors within a chunk.
+    pub max_vectors: usize,
+    /// Maximum estimated size of the json payload of a chunk, in bytes.
+    pub max_bytes: usize,
+    /// Maximum number of chunks being sent at the same time.
+    pub concurrency: usize
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        BatchOptions {
+            max_vectors: MAX_VECTORS_PER_REQUEST,
+            max_bytes: MAX_BYTES_PER_REQUEST,
+            concurrency: 4
+        }
+    }
+}
+
+/// A chunk that failed to upsert.
+#[derive(Debug)]
+pub struct BatchFailure {
+    /// Position of the chunk, in the order vectors were given.
+    pub chunk: usize,
+    /// Ids of the vectors within the chunk, none of which should be assumed to be upserted.
+    pub ids: Vec<String>,
+    /// The error returned for the chunk.
+    pub error: Error
+}
+
+/// Result of a batched upsert.
+#[derive(Debug, Default)]
+pub struct BatchUpsertResponse {
+    /// The combined [`UpsertResponse`] of every chunk that succeeded.
+    pub response: UpsertResponse,
+    /// The chunks that failed, ordered by [`BatchFailure::chunk`].
+    pub failures: Vec<BatchFailure>
+}
+
+impl BatchUpsertResponse {
+
+    /// Returns true if every chunk was upserted.
+    pub fn is_complete(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Bytes used by the `{"namespace":"","vectors":[]}` wrapper of an upsert request, excluding the
+/// namespace itself.
+const REQUEST_OVERHEAD: usize = 30;
+
+/// Splits `vectors` into chunks that respect both limits of `options`. A vector that is larger than
+/// [`BatchOptions::max_bytes`] on its own is placed in a chunk by itself and left for Pinecone to
+/// reject.
+pub(crate) fn chunk_vectors(namespace: &str, vectors: Vec<Vector>, options: &BatchOptions) -> Result<Vec<Vec<Vector>>> {
+    if options.max_vectors == 0 || options.concurrency == 0 {
+        return Err(Error::ArgumentError {
+            name: "BatchOptions".to_string(),
+            found: format!("max_vectors: {}, concurrency: {}", options.max_vectors, options.concurrency),
+            expected: "max_vectors and concurrency greater than 0".to_string()
+        });
+    }
+    let overhead = REQUEST_OVERHEAD + namespace.len();
+    let mut chunks = Vec::new();
+    let mut chunk = Vec::new();
+    let mut chunk_bytes = overhead;
+    for vector in vectors {
+        // the +1 accounts for the comma seperating vectors.
+        let size = estimate_size(&vector) + 1;
+        if !chunk.is_empty() && (chunk.len() >= options.max_vectors || chunk_bytes + size > options.max_bytes) {
+            chunks.push(std::mem::take(&mut chunk));
+            chunk_bytes = overhead;
+        }
+        chunk_bytes += size;
+        chunk.push(vector);
+    }
+    if !chunk.is_empty() {
+        chunks.push(chunk);
+    }
+    Ok(chunks)
+}
+
+fn estimate_size(vector: &Vector) -> usize {
+    serde_json::to_vec(vector).map(|v| v.len()).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod batch_test {
+
+    use super::*;
+
+    fn vectors(count: usize, dimension: usize) -> Vec<Vector> {
+        (0..count).map(|i| Vector{
+            id: i.to_string(),
+            values: vec![0.5; dimension],
+            sparse_values: None,
+            metadata: None
+        }).collect()
+    }
+
+    #[test]
+    fn test_chunk_by_count() {
+        let options = BatchOptions{max_vectors: 10, ..Default::default()};
+        let chunks = chunk_vectors("", vectors(25, 4), &options).unwrap();
+        assert_eq!(chunks.iter().map(Vec::len).collect::<Vec<_>>(), vec![10, 10, 5]);
+        assert_eq!(chunks[2][0].id, "20");
+    }
+
+    #[test]
+    fn test_chunk_by_size() {
+        let size = estimate_size(&vectors(1, 32)[0]) + 1;
+        let options = BatchOptions{max_bytes: REQUEST_OVERHEAD + size * 3, ..Default::default()};
+        let chunks = chunk_vectors("", vectors(7, 32), &options).unwrap();
+        assert_eq!(chunks.iter().map(Vec::len).collect::<Vec<_>>(), vec![3, 3, 1]);
+        for chunk in chunks {
+            let request = serde_json::json!({"namespace": "", "vectors": chunk});
+            assert!(serde_json::to_vec(&request).unwrap().len() <= options.max_bytes);
+        }
+    }
+
+    #[test]
+    fn test_chunk_oversized_vector() {
+        let options = BatchOptions{max_bytes: 10, ..Default::default()};
+        let chunks = chunk_vectors("", vectors(2, 32), &options).unwrap();
+        assert_eq!(chunks.len(), 2);
+    }
+
+    #[test]
+    fn test_chunk_invalid_options() {
+        let options = BatchOptions{concurrency: 0, ..Default::default()};
+        assert!(matches!(chunk_vectors("", vectors(1, 1), &options), Err(Error::ArgumentError{..})));
+    }
+}
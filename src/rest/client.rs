@@ -1,66 +1,332 @@
+use std::{sync::Arc, time::Duration};
+
 use reqwest::{StatusCode, Method};
+use serde::Deserialize;
 use crate::{
-    Result, 
-    Index, models::{CollectionDescription, CreateCollectionRequest, IndexCreateRequest, ClientInfo}, rest::{try_pinecone_request_json, try_pinecone_request_text}
+    Error, Result, 
+    Index, models::{CollectionDescription, CreateCollectionRequest, IndexCreateRequest, ClientInfo, GlobalIndexList, GlobalCollectionList}, rest::{try_pinecone_request_json, try_pinecone_request_text}
 
 };
-use super::{Credentials, Connection};
+use super::{ApiKey, Credentials, CredentialsProvider, Connection, ConnectionConfig, Middleware, Operation, RetryPolicy, WaitOptions, middleware, wait::{self, Poll}};
 
 /// An (authenticated) handle to talk with Pinecone. This is where you first go when you need a
 /// connection. Specific method descriptions and details can be refered to at [Pinecone](https://docs.pinecone.io/reference/list_collections).
+///
+/// The underlying connection pool is reference counted, cloning a [`Client`] or creating an
+/// [`Index`] from it reuses the same pool.
+#[derive(Clone)]
 pub struct Client{
     client: reqwest::Client,
     creds: Credentials,
-    info: ClientInfo
+    info: ClientInfo,
+    config: ConnectionConfig
+}
+
+/// The flavor of Pinecone's control plane a [`Client`] talks to.
+///
+/// The legacy control plane is hosted per environment at `https://controller.{environment}.pinecone.io`
+/// and only supports pod based indexes. The global control plane at `https://api.pinecone.io` also
+/// supports serverless indexes and is required by projects using them. Data plane operations on an
+/// [`Index`] are the same for both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ControlPlane {
+    /// The per environment controller, using `/databases` and `/actions/whoami`.
+    #[default]
+    Legacy,
+    /// The global control plane at `api.pinecone.io`, using `/indexes`.
+    Global
+}
+
+impl ControlPlane {
+
+    /// The version of the global api this crate speaks, sent as `X-Pinecone-API-Version`.
+    pub(crate) const GLOBAL_API_VERSION: &'static str = "2024-07";
+
+    /// Path of the index endpoints, which are followed by the index name.
+    pub(crate) fn indexes_path(&self) -> &'static str {
+        match self {
+            ControlPlane::Legacy => "/databases",
+            ControlPlane::Global => "/indexes"
+        }
+    }
+}
+
+/// Configures and creates a [`Client`], created through [`Client::builder`].
+///
+/// Besides the api key and environment used by [`Client::new`] this allows using a preconfigured
+/// [`reqwest::Client`], setting timeouts and the user agent, and overriding the urls requests are
+/// sent to, such as for pointing the crate at a local stand-in server.
+///
+///```no_run
+///use std::time::Duration;
+///use pinenut::Client;
+///
+///async fn local_client() {
+///    let client = Client::builder("local-api-key")
+///        .controller_url("http://localhost:8080")
+///        .data_plane_url("http://localhost:8080/{index}")
+///        .timeout(Duration::from_secs(10))
+///        .build()
+///        .await
+///        .unwrap();
+///}
+///```
+#[derive(Debug, Clone)]
+pub struct ClientBuilder {
+    credentials: Credentials,
+    control_plane: Option<ControlPlane>,
+    environment: Option<String>,
+    http_client: Option<reqwest::Client>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    user_agent: Option<String>,
+    controller_url: Option<String>,
+    data_plane_url: Option<String>,
+    retry: RetryPolicy,
+    middleware: middleware::Stack
+}
+
+impl ClientBuilder {
+
+    /// Selects the [`ControlPlane`] to talk to, the one of the credentials or
+    /// [`ControlPlane::Legacy`] by default.
+    pub fn control_plane(mut self, control_plane: ControlPlane) -> ClientBuilder {
+        self.control_plane = Some(control_plane);
+        self
+    }
+
+    /// The Pinecone environment, such as `us-east1-gcp`. This is required by the legacy control
+    /// plane unless [`ClientBuilder::controller_url`] is set.
+    pub fn environment(mut self, environment: impl Into<String>) -> ClientBuilder {
+        self.environment = Some(environment.into());
+        self
+    }
+
+    /// Uses a preconfigured [`reqwest::Client`] for every request, for example one with a proxy
+    /// or default headers. This can't be combined with [`ClientBuilder::connect_timeout`].
+    pub fn http_client(mut self, client: reqwest::Client) -> ClientBuilder {
+        self.http_client = Some(client);
+        self
+    }
+
+    /// Timeout applied to every request, from sending it until the response body is read.
+    pub fn timeout(mut self, timeout: Duration) -> ClientBuilder {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Timeout for connecting to Pinecone. Only available when the [`reqwest::Client`] is built
+    /// by this crate.
+    pub fn connect_timeout(mut self, timeout: Duration) -> ClientBuilder {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// The `User-Agent` header sent with every request.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> ClientBuilder {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Overrides the controller url, which defaults to `https://controller.{environment}.pinecone.io`
+    /// for the legacy control plane and `https://api.pinecone.io` for the global one.
+    pub fn controller_url(mut self, url: impl Into<String>) -> ClientBuilder {
+        self.controller_url = Some(url.into().trim_end_matches('/').to_string());
+        self
+    }
+
+    /// Overrides the base url of every index's data plane. Any `{index}` within the url is
+    /// replaced by the name of the index, e.g. `http://localhost:8080/{index}`.
+    pub fn data_plane_url(mut self, url: impl Into<String>) -> ClientBuilder {
+        self.data_plane_url = Some(url.into().trim_end_matches('/').to_string());
+        self
+    }
+
+    /// The [`RetryPolicy`] used by the [`Client`] and every [`Index`] created from it.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> ClientBuilder {
+        self.retry = policy;
+        self
+    }
+
+    /// Adds a [`Middleware`] run around every request of the [`Client`] and of every [`Index`]
+    /// created from it, including the request validating the credentials.
+    pub fn middleware(mut self, middleware: impl Middleware + 'static) -> ClientBuilder {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// Creates the [`Client`] and validates its credentials, see [`Client::new`].
+    ///
+    /// # Error
+    ///
+    /// This function will return an [`Error::ArgumentError`] if there is no environment to build
+    /// the urls with, or if a connect timeout is given alongside a custom [`reqwest::Client`].
+    pub async fn build(self) -> Result<Client> {
+        let client = match self.http_client {
+            Some(client) => {
+                if self.connect_timeout.is_some() {
+                    return Err(Error::ArgumentError {
+                        name: "connect_timeout".to_string(),
+                        found: "a connect timeout alongside a custom reqwest::Client".to_string(),
+                        expected: "the connect timeout to be set on the given reqwest::Client".to_string()
+                    });
+                }
+                client
+            },
+            None => {
+                #[allow(unused_mut)]
+                let mut builder = reqwest::Client::builder();
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(timeout) = self.connect_timeout {
+                    builder = builder.connect_timeout(timeout);
+                }
+                builder.build().map_err(Error::ReqwestError)?
+            }
+        };
+        let control_plane = self.control_plane.or_else(|| self.credentials.control_plane()).unwrap_or_default();
+        let environment = self.environment.or_else(|| self.credentials.environment());
+        let controller_url = self.controller_url.or_else(|| self.credentials.controller_url());
+        let controller_url = match (controller_url, control_plane, environment) {
+            (Some(url), _, _) => url,
+            (None, ControlPlane::Global, _) => "https://api.pinecone.io".to_string(),
+            (None, ControlPlane::Legacy, Some(env)) => format!("https://controller.{}.pinecone.io", env),
+            (None, ControlPlane::Legacy, None) => return Err(Error::ArgumentError {
+                name: "environment".to_string(),
+                found: "None".to_string(),
+                expected: "an environment or a controller url".to_string()
+            })
+        };
+        let mut c = Client{
+            client,
+            info: ClientInfo::default(),
+            config: ConnectionConfig {
+                control_plane,
+                controller_url,
+                data_plane_url: self.data_plane_url,
+                user_agent: self.user_agent,
+                timeout: self.timeout,
+                retry: self.retry,
+                middleware: self.middleware
+            },
+            creds: self.credentials
+        };
+        match c.config.control_plane {
+            ControlPlane::Legacy => {
+                let r = try_pinecone_request_json::<Client, String, ClientInfo>(&c, Operation::new("whoami"), Method::GET, StatusCode::OK, None::<String>, "/actions/whoami", None).await;
+                c.info = r?;
+            },
+            // the global control plane has no whoami, listing indexes validates the api key instead.
+            ControlPlane::Global => {
+                c.list_indexes().await?;
+            }
+        }
+        Ok(c)
+    }
 }
 
 impl Client {
 
-    /// Attempts to validate credentials and return a [`Client`]. 
+    /// Attempts to validate credentials and return a [`Client`] for the legacy control plane.
     ///
     /// If validated it will generate a [`ClientInfo`] which holds the required information
-    /// for valid requests.
+    /// for valid requests. Use [`Client::builder`] for more control over the connection, or to
+    /// connect to the [`ControlPlane::Global`] control plane.
     pub async fn new<D>(api_key: D, environment: D) -> Result<Client>
     where
         D: Into<String>
     {
-        let mut c = Client{
-            client: reqwest::Client::new(),
-            creds: Credentials{
-                api_key: api_key.into(),
-                environment: environment.into()
-            },
-            info: ClientInfo::default()
-        };
-        let r = try_pinecone_request_json::<Client, String, ClientInfo>(&c, Method::GET, StatusCode::OK, None::<String>, "/actions/whoami", None).await;
-        c.info = r?;
-        Ok(c)
+        Client::builder(api_key).environment(environment).build().await
     }
 
-    /// Returns the [`ClientInfo`] generated during on the [`Client::new`] call.
+    /// Returns a [`ClientBuilder`] for the given api key.
+    pub fn builder(api_key: impl Into<String>) -> ClientBuilder {
+        Client::builder_with_credentials(ApiKey::new(api_key))
+    }
+
+    /// Returns a [`ClientBuilder`] taking its api key from `provider` for every request, such as
+    /// [`EnvCredentials`] or a `Profile`. The environment, control plane and controller url of
+    /// the provider are used unless they're given to the builder.
+    ///
+    /// [`EnvCredentials`]: crate::credentials::EnvCredentials
+    pub fn builder_with_credentials(provider: impl CredentialsProvider + 'static) -> ClientBuilder {
+        ClientBuilder {
+            credentials: Credentials::new(Arc::new(provider)),
+            control_plane: None,
+            environment: None,
+            http_client: None,
+            timeout: None,
+            connect_timeout: None,
+            user_agent: None,
+            controller_url: None,
+            data_plane_url: None,
+            retry: RetryPolicy::default(),
+            middleware: Default::default()
+        }
+    }
+
+    /// Returns the [`ControlPlane`] this [`Client`] talks to.
+    pub fn control_plane(&self) -> ControlPlane {
+        self.config.control_plane
+    }
+
+    /// Returns the [`ClientInfo`] generated during on the [`Client::new`] call. The global control
+    /// plane has no such information, in which case this is empty.
     pub fn info(&self) -> &ClientInfo {
         &self.info
     }
 
+    /// Replaces the [`RetryPolicy`] used by this [`Client`] and every [`Index`] created from it
+    /// afterwards.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Client {
+        self.config.retry = policy;
+        self
+    }
+
+    /// Adds a [`Middleware`] run around every request of this [`Client`] and of every [`Index`]
+    /// created from it afterwards, after the middlewares added before it.
+    pub fn with_middleware(mut self, middleware: impl Middleware + 'static) -> Client {
+        self.config.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// Returns the [`RetryPolicy`] requests are sent with.
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.config.retry
+    }
+
     /// Will list all the indexes associated with the given instance of [`Client`].
     pub async fn list_indexes(&self) -> Result<Vec<String>> {
-        try_pinecone_request_json::<Client, String, Vec<String>>(self, Method::GET, StatusCode::OK, None::<String>, "/databases", None).await
+        match self.config.control_plane {
+            ControlPlane::Legacy => try_pinecone_request_json::<Client, String, Vec<String>>(self, Operation::new("list_indexes"), Method::GET, StatusCode::OK, None::<String>, "/databases", None).await,
+            ControlPlane::Global => {
+                let list = try_pinecone_request_json::<Client, String, GlobalIndexList>(self, Operation::new("list_indexes"), Method::GET, StatusCode::OK, None::<String>, "/indexes", None).await?;
+                Ok(list.indexes.into_iter().map(|i| i.name).collect())
+            }
+        }
     }
 
     /// Lists all the collections associated with the given instance [`Client`].
     pub async fn list_collections(&self) -> Result<Vec<String>> {
-        try_pinecone_request_json::<Client, String, Vec<String>>(self, Method::GET, StatusCode::OK, None::<String>, "/collections", None).await
+        match self.config.control_plane {
+            ControlPlane::Legacy => try_pinecone_request_json::<Client, String, Vec<String>>(self, Operation::new("list_collections"), Method::GET, StatusCode::OK, None::<String>, "/collections", None).await,
+            ControlPlane::Global => {
+                let list = try_pinecone_request_json::<Client, String, GlobalCollectionList>(self, Operation::new("list_collections"), Method::GET, StatusCode::OK, None::<String>, "/collections", None).await?;
+                Ok(list.collections.into_iter().map(|c| c.name).collect())
+            }
+        }
     }
 
     /// Creates a new collection. 
     /// 
     /// For more information on Collections vist [Pinecone](https://docs.pinecone.io/docs/collections).
+    /// The collection is created in the background, see [`Client::wait_until_collection_ready`].
     pub async fn create_collection(&self, name: impl Into<String>, source_index: impl AsRef<str>) -> Result<String> {
         let request = CreateCollectionRequest{
             name: name.into(),
             source: source_index.as_ref().to_string()
         };
-        try_pinecone_request_text::<Client, CreateCollectionRequest>(self, Method::POST, StatusCode::CREATED, None::<String>, "/collections", Some(&request)).await
+        try_pinecone_request_text::<Client, CreateCollectionRequest>(self, Operation::new("create_collection").non_idempotent(), Method::POST, StatusCode::CREATED, None::<String>, "/collections", Some(&request)).await
     }
 
     /// Attempts to get a description of a collection. 
@@ -69,32 +335,109 @@ impl Client {
     ///
     /// This function will error if the collection does not exist
     pub async fn describe_collection(&self, name: impl AsRef<str>) -> Result<CollectionDescription> {
-        try_pinecone_request_json::<Client, String, CollectionDescription>(self, Method::GET, StatusCode::OK, None::<String>, format!("/collections/{}", name.as_ref()), None).await
+        try_pinecone_request_json::<Client, String, CollectionDescription>(self, Operation::new("describe_collection"), Method::GET, StatusCode::OK, None::<String>, format!("/collections/{}", name.as_ref()), None).await
     }
 
-    /// Deletes a given collection.
+    /// Deletes a given collection. The collection is deleted in the background, see
+    /// [`Client::wait_until_collection_deleted`].
     pub async fn delete_collection(&self, name: impl AsRef<str>) -> Result<String> {
-        try_pinecone_request_text::<Client, String>(self, Method::DELETE, StatusCode::ACCEPTED, None::<String>, format!("/collections/{}", name.as_ref()), None).await
+        try_pinecone_request_text::<Client, String>(self, Operation::new("delete_collection"), Method::DELETE, StatusCode::ACCEPTED, None::<String>, format!("/collections/{}", name.as_ref()), None).await
     }
 
     /// Creates a collection.
     ///
     /// The index create operation will take time even after the response is [`StatusCode::CREATED`] and index 
-    /// operations will not function until this is the case. If creating an index ensure that you
-    /// add a resonable delay.
+    /// operations will not function until this is the case. If creating an index use
+    /// [`Index::wait_until_ready`] to wait until it can be used.
     ///
     /// This will error with status code 409 [`StatusCode::CONFLICT`] if the name already exists.
     /// This should be checked for if you're trying to validate if a given index exists before
     /// doing operations.
     ///
+    /// The request is checked with [`IndexCreateRequest::validate`] first. The global control plane
+    /// requires [`IndexCreateRequest::spec`] while the legacy one doesn't support it, an
+    /// [`Error::ArgumentError`] is returned without making a request otherwise.
     pub async fn create_index(&self, data: IndexCreateRequest) -> Result<String> {
-        try_pinecone_request_text::<Client, IndexCreateRequest>(self, Method::POST, StatusCode::CREATED, None::<String>, "/databases", Some(&data)).await
+        data.validate()?;
+        match (self.config.control_plane, &data.spec) {
+            (ControlPlane::Legacy, Some(_)) | (ControlPlane::Global, None) => {
+                return Err(Error::ArgumentError {
+                    name: "spec".to_string(),
+                    found: format!("{:?}", data.spec),
+                    expected: format!("a spec if and only if the control plane is global, found {:?}", self.config.control_plane)
+                });
+            },
+            _ => {}
+        }
+        try_pinecone_request_text::<Client, IndexCreateRequest>(self, Operation::new("create_index").non_idempotent(), Method::POST, StatusCode::CREATED, None::<String>, self.config.control_plane.indexes_path(), Some(&data)).await
+    }
+
+    /// Waits until the index `name` no longer exists, polling its description with the backoff
+    /// and deadline of `options`. This should be used after [`Index::delete`].
+    ///
+    /// # Error
+    ///
+    /// Returns an [`Error::WaitTimeout`] if the index still exists after [`WaitOptions::timeout`].
+    pub async fn wait_until_index_deleted(&self, name: impl AsRef<str>, options: WaitOptions) -> Result<()> {
+        let index = self.index(name.as_ref());
+        wait::poll(name.as_ref(), &options, || async {
+            match index.fetch_description().await {
+                Ok(description) => Ok(Poll::Pending(description.status.state.to_string())),
+                Err(Error::NotFound(_)) => Ok(Poll::Done(())),
+                Err(err) => Err(err)
+            }
+        }).await
+    }
+
+    /// Waits until the collection `name` is ready, polling [`Client::describe_collection`] with
+    /// the backoff and deadline of `options`, and returns its final description.
+    ///
+    /// # Error
+    ///
+    /// Returns an [`Error::WaitTimeout`] if the collection isn't ready within
+    /// [`WaitOptions::timeout`].
+    pub async fn wait_until_collection_ready(&self, name: impl AsRef<str>, options: WaitOptions) -> Result<CollectionDescription> {
+        wait::poll(name.as_ref(), &options, || async {
+            let description = self.describe_collection(name.as_ref()).await?;
+            if description.status == "Ready" {
+                return Ok(Poll::Done(description));
+            }
+            Ok(Poll::Pending(description.status))
+        }).await
+    }
+
+    /// Waits until the collection `name` no longer exists, polling [`Client::describe_collection`]
+    /// with the backoff and deadline of `options`. This should be used after
+    /// [`Client::delete_collection`].
+    ///
+    /// # Error
+    ///
+    /// Returns an [`Error::WaitTimeout`] if the collection still exists after
+    /// [`WaitOptions::timeout`].
+    pub async fn wait_until_collection_deleted(&self, name: impl AsRef<str>, options: WaitOptions) -> Result<()> {
+        wait::poll(name.as_ref(), &options, || async {
+            match self.describe_collection(name.as_ref()).await {
+                Ok(description) => Ok(Poll::Pending(description.status)),
+                Err(Error::NotFound(_)) => Ok(Poll::Done(())),
+                Err(err) => Err(err)
+            }
+        }).await
     }
+
     /// Creates and returns an Index object that can be used to run index specific operations, it
     /// is the primary way you interface with the Index Api. The index created will not be a
     /// validated index and therefor should be validated using the [`Index::describe`] method.
     pub fn index(&self, name: impl Into<String>) -> Index {
-        Index::new::<Self>(self, name, self.info())
+        Index::new::<Self>(self, name, None)
+    }
+
+    /// Creates an [`Index`] that sends data plane requests to a known `host`, such as the
+    /// [`IndexStatusDescription::host`] of a previous describe, without describing the index
+    /// first. Hosts without a scheme are connected to over https.
+    ///
+    /// [`IndexStatusDescription::host`]: crate::models::IndexStatusDescription::host
+    pub fn index_by_host(&self, name: impl Into<String>, host: impl Into<String>) -> Index {
+        Index::new::<Self>(self, name, Some(host.into()))
     }
 }
 
@@ -106,9 +449,33 @@ impl Connection for Client {
     fn credentials(&self) -> &Credentials {
         &self.creds
     }
+
+    fn config(&self) -> &ConnectionConfig {
+        &self.config
+    }
 }
 
 
+/// Creates a client without validating its api key, for tests of requests failing before they're
+/// sent. Requests that are sent fail, as nothing listens at its controller url.
+#[cfg(test)]
+pub(crate) fn unconnected(control_plane: ControlPlane) -> Client {
+    Client {
+        client: reqwest::Client::new(),
+        creds: Credentials::new(Arc::new(ApiKey::new("unconnected"))),
+        info: ClientInfo::default(),
+        config: ConnectionConfig {
+            control_plane,
+            controller_url: "http://127.0.0.1:1".to_string(),
+            data_plane_url: None,
+            user_agent: None,
+            timeout: None,
+            retry: RetryPolicy::none(),
+            middleware: Default::default()
+        }
+    }
+}
+
 #[cfg(test)]
 mod client_test {
 
@@ -118,118 +485,339 @@ mod client_test {
     #[cfg(target_arch="wasm32")]
     use wasm_bindgen_test::*;
 
-    use crate::{Error, models::Metric};
+    use crate::{Error, models::{Metric, IndexSpec, ServerlessSpec, Cloud}};
+
+    #[cfg(not(target_arch="wasm32"))]
+    use crate::rest::stub::{Stub, StubResponse};
 
-    async fn create_client() -> Client {
-        Client::new(
-            env!("PINECONE_API_KEY"),
-            env!("PINECONE_ENV")
-        ).await.unwrap()
+    #[cfg(not(target_arch="wasm32"))]
+    use crate::mock::MockPinecone;
+
+    /// Connects to a new [`MockPinecone`], which has to be kept alive alongside the client.
+    #[cfg(not(target_arch="wasm32"))]
+    async fn create_client() -> (MockPinecone, Client) {
+        let mock = MockPinecone::start().await;
+        let client = mock.client().await.unwrap();
+        (mock, client)
     }
 
-    #[cfg_attr(not(target_arch="wasm32"), tokio::test)]
-    #[cfg_attr(target_arch="wasm32", wasm_bindgen_test)]
+    #[cfg(not(target_arch="wasm32"))]
+    async fn create_test_index(client: &Client) {
+        let request = IndexCreateRequest::builder(TEST_INDEX, 32).build().unwrap();
+        client.create_index(request).await.unwrap();
+    }
+
+    #[cfg(not(target_arch="wasm32"))]
+    const TEST_INDEX: &str = "pinenut";
+
+    #[cfg(not(target_arch="wasm32"))]
+    #[tokio::test]
     async fn test_create_client() {
-        let c = Client::new(
-            env!("PINECONE_API_KEY"),
-            env!("PINECONE_ENV")
-        ).await;
+        let mock = MockPinecone::start().await;
+        let c = mock.client_builder().build().await;
         match c {
-            Ok(_) => assert!(true),
+            Ok(c) => assert_eq!(c.info().project_name, "mock"),
             Err(err) => panic!("failed to create client {:?}", err)
         }
-        assert!(true)
+        assert!(Client::builder("wrong").controller_url(mock.url()).build().await.is_err());
+    }
+
+    #[cfg(not(target_arch="wasm32"))]
+    #[tokio::test]
+    async fn test_builder_overrides() {
+        let stub = Stub::routes(vec![
+            (Method::GET, "/actions/whoami", StubResponse::new(StatusCode::OK, r#"{"project_name":"stubbed","user_label":null,"user_name":null}"#)),
+            (Method::GET, "/databases", StubResponse::new(StatusCode::OK, r#"["local"]"#)),
+            (Method::GET, "/local/describe_index_stats", StubResponse::new(StatusCode::OK, r#"{"namespaces":{},"dimension":4,"indexFullness":0,"totalVectorCount":0}"#))
+        ]);
+        let client = Client::builder("stub-key")
+            .controller_url(stub.url())
+            .data_plane_url(format!("{}/{{index}}", stub.url()))
+            .user_agent("pinenut-test")
+            .timeout(std::time::Duration::from_secs(5))
+            .build()
+            .await
+            .unwrap();
+        assert_eq!(client.info().project_name, "stubbed");
+        assert_eq!(client.list_indexes().await.unwrap(), vec!["local".to_string()]);
+
+        let index = client.index("local");
+        assert_eq!(index.url().unwrap(), format!("{}/local", stub.url()));
+        assert_eq!(index.describe_stats().await.unwrap().dimension, 4);
+
+        let requests = stub.requests();
+        assert_eq!(requests.iter().map(|r| r.path.as_str()).collect::<Vec<_>>(), vec!["/actions/whoami", "/databases", "/local/describe_index_stats"]);
+        for request in requests {
+            assert_eq!(request.method, Method::GET);
+            assert_eq!(request.headers["Api-Key"], "stub-key");
+            assert_eq!(request.headers["user-agent"], "pinenut-test");
+        }
+    }
+
+    #[cfg(not(target_arch="wasm32"))]
+    #[tokio::test]
+    async fn test_index_shares_connection_pool() {
+        let stats = StubResponse::new(StatusCode::OK, r#"{"namespaces":{},"dimension":4,"indexFullness":0,"totalVectorCount":0}"#);
+        let stub = Stub::routes(vec![
+            (Method::GET, "/actions/whoami", StubResponse::new(StatusCode::OK, r#"{"project_name":"stubbed","user_label":null,"user_name":null}"#)),
+            (Method::GET, "/first/describe_index_stats", stats.clone()),
+            (Method::GET, "/second/describe_index_stats", stats)
+        ]);
+        let client = Client::builder("stub-key")
+            .controller_url(stub.url())
+            .data_plane_url(format!("{}/{{index}}", stub.url()))
+            .build()
+            .await
+            .unwrap();
+        client.index("first").describe_stats().await.unwrap();
+        client.clone().index("second").describe_stats().await.unwrap();
+
+        let remotes: Vec<_> = stub.requests().iter().map(|r| r.remote).collect();
+        assert_eq!(remotes.len(), 3);
+        assert!(remotes.iter().all(|r| *r == remotes[0]), "requests used several connections: {:?}", remotes);
+    }
+
+    #[cfg(not(target_arch="wasm32"))]
+    const GLOBAL_INDEX: &str = r#"{"name":"serverless","dimension":8,"metric":"cosine","host":"serverless-abc.svc.aped-4627-b74a.pinecone.io","spec":{"serverless":{"cloud":"aws","region":"us-east-1"}},"status":{"ready":true,"state":"Ready"},"deletion_protection":"disabled"}"#;
+
+    #[cfg(not(target_arch="wasm32"))]
+    #[tokio::test]
+    async fn test_global_control_plane() {
+        let stub = Stub::routes(vec![
+            (Method::GET, "/indexes", StubResponse::new(StatusCode::OK, format!(r#"{{"indexes":[{}]}}"#, GLOBAL_INDEX))),
+            (Method::GET, "/indexes/serverless", StubResponse::new(StatusCode::OK, GLOBAL_INDEX)),
+            (Method::GET, "/collections", StubResponse::new(StatusCode::OK, r#"{"collections":[{"name":"backup","size":10,"status":"Ready","dimension":8,"vector_count":1,"environment":"us-east1-gcp"}]}"#)),
+            (Method::POST, "/indexes", StubResponse::new(StatusCode::CREATED, GLOBAL_INDEX))
+        ]);
+        let client = Client::builder("stub-key")
+            .control_plane(ControlPlane::Global)
+            .controller_url(stub.url())
+            .build()
+            .await
+            .unwrap();
+        assert_eq!(client.control_plane(), ControlPlane::Global);
+        assert_eq!(client.list_indexes().await.unwrap(), vec!["serverless".to_string()]);
+        assert_eq!(client.list_collections().await.unwrap(), vec!["backup".to_string()]);
+
+        let index = client.index("serverless");
+        let description = index.describe().await.unwrap();
+        assert_eq!(description.database.dimension, 8);
+        assert_eq!(description.spec, Some(IndexSpec::Serverless(ServerlessSpec{cloud: Cloud::AWS, region: "us-east-1".to_string()})));
+        assert_eq!(index.url().unwrap(), "https://serverless-abc.svc.aped-4627-b74a.pinecone.io");
+
+        let request = IndexCreateRequest::builder("serverless", 8)
+            .metric(Metric::COSINE)
+            .spec(IndexSpec::Serverless(ServerlessSpec{cloud: Cloud::AWS, region: "us-east-1".to_string()}))
+            .build()
+            .unwrap();
+        client.create_index(request).await.unwrap();
+
+        let requests = stub.requests();
+        assert!(requests.iter().all(|r| r.headers["X-Pinecone-API-Version"] == ControlPlane::GLOBAL_API_VERSION));
+        assert!(requests.iter().all(|r| r.path != "/actions/whoami"));
+        let create = requests.iter().find(|r| r.method == Method::POST).unwrap();
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&create.body).unwrap(), serde_json::json!({
+            "name": "serverless",
+            "dimension": 8,
+            "metric": "cosine",
+            "spec": {"serverless": {"cloud": "aws", "region": "us-east-1"}}
+        }));
     }
 
     #[cfg_attr(not(target_arch="wasm32"), tokio::test)]
     #[cfg_attr(target_arch="wasm32", wasm_bindgen_test)]
-    async fn test_list_indexes() {
-        let client = create_client().await;
-        match client.list_indexes().await {
-            Ok(list) => {
-                assert!(!list.is_empty(), "working? {:?}", list);
-            },
-            Err(error) => panic!("Unable to list indexes: {:?}", error)
+    async fn test_global_create_index_requires_spec() {
+        // the client can't send requests, so the errors below are raised before sending any.
+        let client = unconnected(ControlPlane::Global);
+        let request = IndexCreateRequest{name: "pods".to_string(), dimension: 8, ..Default::default()};
+        match client.create_index(request).await {
+            Err(Error::ArgumentError{name, ..}) => assert_eq!(name, "spec"),
+            other => panic!("Expected an argument error: {:?}", other)
+        }
+        let request = IndexCreateRequest{name: "Pods".to_string(), dimension: 8, ..Default::default()};
+        match client.create_index(request).await {
+            Err(Error::ArgumentError{name, ..}) => assert_eq!(name, "name"),
+            other => panic!("Expected an argument error: {:?}", other)
         }
     }
 
     #[cfg_attr(not(target_arch="wasm32"), tokio::test)]
     #[cfg_attr(target_arch="wasm32", wasm_bindgen_test)]
-    async fn test_create_index() {
-        let client = create_client().await;
-        match client.create_index(IndexCreateRequest{
-            name: env!("PINECONE_INDEX_NAME").to_string(),
-            dimension: 32,
-            metric: Metric::EUCLIDEAN.to_string()
-        }).await {
-            Ok(_) => assert!(true),
-            Err(error) => {
-                match error {
-                    Error::PineconeResponseError(code,typ,msg) => {
-                        if code == StatusCode::CONFLICT || code == StatusCode::BAD_REQUEST {
-                            assert!(true);
-                            return;
-                        }
-                        panic!("Unable to create index: {:?}", Error::PineconeResponseError(code, typ, msg))
-                    },
-                    _ => {
-                        panic!("Unable to create index: {:?}", error)
-                    }
-                }
-            }
-
+    async fn test_builder_requires_environment() {
+        match Client::builder("key").data_plane_url("http://127.0.0.1:1").build().await {
+            Err(Error::ArgumentError{name, ..}) => assert_eq!(name, "environment"),
+            other => panic!("Expected an argument error: {:?}", other.map(|c| c.info().clone()))
         }
     }
 
     #[cfg_attr(not(target_arch="wasm32"), tokio::test)]
     #[cfg_attr(target_arch="wasm32", wasm_bindgen_test)]
-    async fn test_create_collection() {
-        let client = create_client().await;
-        match client.create_collection("testcollection", env!("PINECONE_INDEX_NAME")).await {
-            Ok(_) => {
-                assert!(true)
+    async fn test_builder_rejects_connect_timeout_with_http_client() {
+        let builder = Client::builder("key")
+            .environment("env")
+            .http_client(reqwest::Client::new())
+            .connect_timeout(std::time::Duration::from_secs(1));
+        match builder.build().await {
+            Err(Error::ArgumentError{name, ..}) => assert_eq!(name, "connect_timeout"),
+            other => panic!("Expected an argument error: {:?}", other.map(|c| c.info().clone()))
+        }
+    }
+
+    #[cfg(not(target_arch="wasm32"))]
+    fn fast_wait(timeout: Duration) -> WaitOptions {
+        WaitOptions {
+            timeout,
+            initial_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5)
+        }
+    }
+
+    #[cfg(not(target_arch="wasm32"))]
+    async fn global_client(stub: &Stub) -> Client {
+        Client::builder("stub-key")
+            .control_plane(ControlPlane::Global)
+            .controller_url(stub.url())
+            .retry_policy(RetryPolicy::none())
+            .build()
+            .await
+            .unwrap()
+    }
+
+    #[cfg(not(target_arch="wasm32"))]
+    #[tokio::test]
+    async fn test_wait_until_ready() {
+        let initializing = GLOBAL_INDEX.replace(r#"{"ready":true,"state":"Ready"}"#, r#"{"ready":false,"state":"Initializing"}"#);
+        let stub = Stub::start(move |hit, req| match (hit, req.path.as_str()) {
+            (_, "/indexes") => StubResponse::new(StatusCode::OK, r#"{"indexes":[]}"#),
+            (1..=3, _) => StubResponse::new(StatusCode::OK, initializing.clone()),
+            _ => StubResponse::new(StatusCode::OK, GLOBAL_INDEX)
+        });
+        let client = global_client(&stub).await;
+        let index = client.index("serverless");
+        let description = index.wait_until_ready(fast_wait(Duration::from_secs(5))).await.unwrap();
+        assert!(description.status.ready);
+        assert_eq!(stub.hits(), 5);
+        assert_eq!(index.url().unwrap(), "https://serverless-abc.svc.aped-4627-b74a.pinecone.io");
+    }
+
+    #[cfg(not(target_arch="wasm32"))]
+    #[tokio::test]
+    async fn test_wait_until_ready_failures() {
+        let failed = GLOBAL_INDEX.replace(r#"{"ready":true,"state":"Ready"}"#, r#"{"ready":false,"state":"InitializationFailed"}"#);
+        let stub = Stub::routes(vec![
+            (Method::GET, "/indexes", StubResponse::new(StatusCode::OK, r#"{"indexes":[]}"#)),
+            (Method::GET, "/indexes/serverless", StubResponse::new(StatusCode::OK, failed))
+        ]);
+        let client = global_client(&stub).await;
+        match client.index("serverless").wait_until_ready(fast_wait(Duration::from_secs(5))).await {
+            Err(Error::InitializationFailed{name}) => assert_eq!(name, "serverless"),
+            other => panic!("Expected an initialization failure: {:?}", other)
+        }
+
+        let scaling = GLOBAL_INDEX.replace(r#"{"ready":true,"state":"Ready"}"#, r#"{"ready":true,"state":"ScalingUp"}"#);
+        let stub = Stub::routes(vec![
+            (Method::GET, "/indexes", StubResponse::new(StatusCode::OK, r#"{"indexes":[]}"#)),
+            (Method::GET, "/indexes/serverless", StubResponse::new(StatusCode::OK, scaling))
+        ]);
+        let client = global_client(&stub).await;
+        match client.index("serverless").wait_until_ready(fast_wait(Duration::from_millis(50))).await {
+            Err(Error::WaitTimeout{name, state, waited}) => {
+                assert_eq!(name, "serverless");
+                assert_eq!(state, "ScalingUp");
+                assert!(waited >= Duration::from_millis(50));
             },
-            Err(error) => {
-                match error {
-                    Error::PineconeResponseError(code,typ,msg) => {
-                        if code == StatusCode::BAD_REQUEST {
-                            assert!(true);
-                            return;
-                        }
-                        panic!("Unable to create collection: {:?}", Error::PineconeResponseError(code, typ, msg))
-                    },
-                    _ => {
-                        panic!("Unable to create collection: {:?}", error)
-                    }
-                }
-            }
+            other => panic!("Expected a timeout: {:?}", other)
         }
+        assert!(stub.hits() > 2);
     }
 
+    #[cfg(not(target_arch="wasm32"))]
+    #[tokio::test]
+    async fn test_wait_until_deleted() {
+        let stub = Stub::start(|hit, req| match (hit, req.path.as_str()) {
+            (_, "/indexes") => StubResponse::new(StatusCode::OK, r#"{"indexes":[]}"#),
+            (1 | 2, "/indexes/serverless") => StubResponse::new(StatusCode::OK, GLOBAL_INDEX.replace(r#""state":"Ready""#, r#""state":"Terminating""#)),
+            (4 | 5, "/collections/backup") => StubResponse::new(StatusCode::OK, r#"{"name":"backup","size":10,"status":"Terminating"}"#),
+            _ => StubResponse::new(StatusCode::NOT_FOUND, "")
+        });
+        let client = global_client(&stub).await;
+        client.wait_until_index_deleted("serverless", fast_wait(Duration::from_secs(5))).await.unwrap();
+        assert_eq!(stub.hits(), 4);
+        client.wait_until_collection_deleted("backup", fast_wait(Duration::from_secs(5))).await.unwrap();
+        assert_eq!(stub.hits(), 7);
+    }
 
-    #[cfg_attr(not(target_arch="wasm32"), tokio::test)]
-    #[cfg_attr(target_arch="wasm32", wasm_bindgen_test)]
-    async fn test_delete_collection(){
-        let client = create_client().await;
-        match client.delete_collection("testcollection").await {
-            Ok(_) => {
-                assert!(true)
+    #[cfg(not(target_arch="wasm32"))]
+    #[tokio::test]
+    async fn test_wait_until_collection_ready() {
+        let stub = Stub::start(|hit, req| match (hit, req.path.as_str()) {
+            (_, "/indexes") => StubResponse::new(StatusCode::OK, r#"{"indexes":[]}"#),
+            (1 | 2, _) => StubResponse::new(StatusCode::OK, r#"{"name":"backup","size":0,"status":"Initializing"}"#),
+            (3, _) => StubResponse::new(StatusCode::OK, r#"{"name":"backup","size":10,"status":"Ready","dimension":8,"vector_count":1}"#),
+            _ => StubResponse::new(StatusCode::UNAUTHORIZED, "")
+        });
+        let client = global_client(&stub).await;
+        let description = client.wait_until_collection_ready("backup", fast_wait(Duration::from_secs(5))).await.unwrap();
+        assert_eq!(description.status, "Ready");
+        assert_eq!(description.vector_count, Some(1));
+        assert!(client.wait_until_collection_ready("backup", fast_wait(Duration::from_secs(5))).await.is_err());
+        assert_eq!(stub.hits(), 5);
+    }
+
+    #[cfg(not(target_arch="wasm32"))]
+    #[tokio::test]
+    async fn test_list_indexes() {
+        let (_mock, client) = create_client().await;
+        create_test_index(&client).await;
+        match client.list_indexes().await {
+            Ok(list) => {
+                assert_eq!(list, vec![TEST_INDEX.to_string()]);
             },
-            Err(error) => {
-                match error {
-                    Error::PineconeResponseError(code,typ,msg) => {
-                        if code == StatusCode::BAD_REQUEST {
-                            assert!(true);
-                            return;
-                        }
-                        panic!("Unable to delete collection: {:?}", Error::PineconeResponseError(code, typ, msg))
-                    },
-                    _ => {
-                        panic!("Unable to delete collection: {:?}", error)
-                    }
-                }
-            }
+            Err(error) => panic!("Unable to list indexes: {:?}", error)
+        }
+    }
+
+    #[cfg(not(target_arch="wasm32"))]
+    #[tokio::test]
+    async fn test_create_index() {
+        let (_mock, client) = create_client().await;
+        let request = IndexCreateRequest{
+            name: TEST_INDEX.to_string(),
+            dimension: 32,
+            metric: Metric::EUCLIDEAN,
+            ..Default::default()
+        };
+        client.create_index(request.clone()).await.unwrap();
+        match client.create_index(request).await {
+            Err(Error::AlreadyExists(err)) => assert_eq!(err.path, "/databases"),
+            other => panic!("Expected a conflict: {:?}", other)
         }
+        let description = client.index(TEST_INDEX).describe().await.unwrap().clone();
+        assert_eq!(description.database.metric, Metric::EUCLIDEAN);
+        assert_eq!(description.database.dimension, 32);
     }
-} 
 
+    #[cfg(not(target_arch="wasm32"))]
+    #[tokio::test]
+    async fn test_create_collection() {
+        let (_mock, client) = create_client().await;
+        create_test_index(&client).await;
+        client.create_collection("testcollection", TEST_INDEX).await.unwrap();
+        assert_eq!(client.list_collections().await.unwrap(), vec!["testcollection".to_string()]);
+        match client.create_collection("othercollection", "missing").await {
+            Err(Error::NotFound(err)) => assert!(err.message.contains("missing"), "{}", err.message),
+            other => panic!("Expected a missing source index: {:?}", other)
+        }
+    }
+
+    #[cfg(not(target_arch="wasm32"))]
+    #[tokio::test]
+    async fn test_delete_collection(){
+        let (_mock, client) = create_client().await;
+        create_test_index(&client).await;
+        client.create_collection("testcollection", TEST_INDEX).await.unwrap();
+        client.delete_collection("testcollection").await.unwrap();
+        client.wait_until_collection_deleted("testcollection", WaitOptions::default()).await.unwrap();
+        assert!(client.delete_collection("testcollection").await.is_err());
+    }
+}
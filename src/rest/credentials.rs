@@ -0,0 +1,604 @@
+//! Sources of the api key a [`Client`] authenticates with, see [`CredentialsProvider`].
+//!
+//! Besides a plain api key given to [`Client::builder`], keys can be read from the environment
+//! with [`EnvCredentials`], from named profiles of a TOML file with `Profile` when the `profiles`
+//! feature is enabled (files can't be read on wasm, where profiles are only constructed by hand),
+//! or held by a [`RefreshableCredentials`] which can be rotated while the [`Client`] and its
+//! indexes are in use.
+//!
+//! Keys are held as an [`ApiKey`], which is erased from memory when dropped and never shows up in
+//! `Debug` or `Display` output, so that it doesn't end up in logs.
+//!
+//! [`Client`]: crate::Client
+//! [`Client::builder`]: crate::Client::builder
+
+use std::{
+    fmt,
+    sync::{Arc, RwLock},
+    time::Duration
+};
+#[cfg(all(feature = "profiles", not(target_arch = "wasm32")))]
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf}
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+use serde::Deserialize;
+use zeroize::Zeroize;
+#[cfg(all(feature = "profiles", not(target_arch = "wasm32")))]
+use zeroize::Zeroizing;
+
+use crate::{Error, Result};
+use super::ControlPlane;
+
+/// The variable [`EnvCredentials`] reads the api key from by default.
+pub const API_KEY_VAR: &str = "PINECONE_API_KEY";
+
+/// The variable [`EnvCredentials`] reads the environment from by default.
+pub const ENVIRONMENT_VAR: &str = "PINECONE_ENV";
+
+/// A Pinecone api key. The key is overwritten with zeros when dropped and is redacted by its
+/// `Debug` and `Display` implementations, use [`ApiKey::expose`] to read it.
+///
+/// Copies of the key made while sending a request, such as the `Api-Key` header, aren't erased,
+/// but the header is marked as sensitive so that the http client doesn't print it either.
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+#[serde(transparent)]
+pub struct ApiKey(String);
+
+impl ApiKey {
+
+    /// Wraps a key.
+    pub fn new(api_key: impl Into<String>) -> ApiKey {
+        ApiKey(api_key.into())
+    }
+
+    /// The key itself.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for ApiKey {
+    fn from(api_key: String) -> Self {
+        ApiKey(api_key)
+    }
+}
+
+impl From<&str> for ApiKey {
+    fn from(api_key: &str) -> Self {
+        ApiKey(api_key.to_string())
+    }
+}
+
+impl Drop for ApiKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl fmt::Debug for ApiKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ApiKey(<redacted>)")
+    }
+}
+
+impl fmt::Display for ApiKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
+/// Provides the api key sent with every request of a [`Client`] and of every [`Index`] created
+/// from it, given to [`Client::builder_with_credentials`].
+///
+/// The key is requested again for every request, so a provider returning a new key rotates it
+/// for every [`Index`] at once. Providers should therefore return quickly, doing any slow work
+/// ahead of time, like [`RefreshableCredentials`] does.
+///
+/// [`Client`]: crate::Client
+/// [`Index`]: crate::Index
+/// [`Client::builder_with_credentials`]: crate::Client::builder_with_credentials
+pub trait CredentialsProvider: Send + Sync {
+
+    /// The api key to send with the next request.
+    fn api_key(&self) -> Result<ApiKey>;
+
+    /// The environment of the legacy control plane, used when none is given to the
+    /// [`ClientBuilder`].
+    ///
+    /// [`ClientBuilder`]: crate::ClientBuilder
+    fn environment(&self) -> Option<String> {
+        None
+    }
+
+    /// The control plane to talk to, used when none is given to the [`ClientBuilder`].
+    ///
+    /// [`ClientBuilder`]: crate::ClientBuilder
+    fn control_plane(&self) -> Option<ControlPlane> {
+        None
+    }
+
+    /// The controller url, used when none is given to the [`ClientBuilder`].
+    ///
+    /// [`ClientBuilder`]: crate::ClientBuilder
+    fn controller_url(&self) -> Option<String> {
+        None
+    }
+}
+
+impl CredentialsProvider for ApiKey {
+    fn api_key(&self) -> Result<ApiKey> {
+        Ok(self.clone())
+    }
+}
+
+impl<P: CredentialsProvider + ?Sized> CredentialsProvider for Arc<P> {
+    fn api_key(&self) -> Result<ApiKey> {
+        (**self).api_key()
+    }
+
+    fn environment(&self) -> Option<String> {
+        (**self).environment()
+    }
+
+    fn control_plane(&self) -> Option<ControlPlane> {
+        (**self).control_plane()
+    }
+
+    fn controller_url(&self) -> Option<String> {
+        (**self).controller_url()
+    }
+}
+
+/// Reads the api key and environment from environment variables, [`API_KEY_VAR`] and
+/// [`ENVIRONMENT_VAR`] by default. The variables are read for every request.
+#[derive(Debug, Clone)]
+pub struct EnvCredentials {
+    api_key_var: String,
+    environment_var: String
+}
+
+impl EnvCredentials {
+
+    /// Reads the default variables.
+    pub fn new() -> EnvCredentials {
+        EnvCredentials::with_vars(API_KEY_VAR, ENVIRONMENT_VAR)
+    }
+
+    /// Reads the given variables instead of the default ones.
+    pub fn with_vars(api_key_var: impl Into<String>, environment_var: impl Into<String>) -> EnvCredentials {
+        EnvCredentials {
+            api_key_var: api_key_var.into(),
+            environment_var: environment_var.into()
+        }
+    }
+}
+
+impl Default for EnvCredentials {
+    fn default() -> Self {
+        EnvCredentials::new()
+    }
+}
+
+impl CredentialsProvider for EnvCredentials {
+    fn api_key(&self) -> Result<ApiKey> {
+        std::env::var(&self.api_key_var)
+            .map(ApiKey::from)
+            .map_err(|err| Error::CredentialsError(format!("{} is not usable: {}", self.api_key_var, err)))
+    }
+
+    fn environment(&self) -> Option<String> {
+        std::env::var(&self.environment_var).ok()
+    }
+}
+
+/// A named profile of a credentials file, by default `~/.config/pinenut`. Each profile is a
+/// table holding an api key, alongside optional connection settings which a [`ClientBuilder`]
+/// created by [`Client::builder_with_credentials`] uses unless it's given its own:
+///
+/// ```toml
+/// [default]
+/// api_key = "..."
+/// environment = "us-east1-gcp"
+///
+/// [serverless]
+/// api_key = "..."
+/// control_plane = "global"
+/// ```
+///
+/// [`ClientBuilder`]: crate::ClientBuilder
+/// [`Client::builder_with_credentials`]: crate::Client::builder_with_credentials
+#[cfg(feature = "profiles")]
+#[derive(Debug, Clone, Deserialize)]
+pub struct Profile {
+    /// The api key of the profile.
+    pub api_key: ApiKey,
+    /// The environment of the legacy control plane.
+    #[serde(default)]
+    pub environment: Option<String>,
+    /// The control plane to talk to.
+    #[serde(default)]
+    pub control_plane: Option<ControlPlane>,
+    /// Overrides the controller url, see [`ClientBuilder::controller_url`].
+    ///
+    /// [`ClientBuilder::controller_url`]: crate::ClientBuilder::controller_url
+    #[serde(default)]
+    pub controller_url: Option<String>
+}
+
+#[cfg(feature = "profiles")]
+impl Profile {
+
+    /// The name of the profile used when none is given.
+    pub const DEFAULT: &'static str = "default";
+
+    /// The default credentials file, `$XDG_CONFIG_HOME/pinenut` or `~/.config/pinenut`, if a home
+    /// directory can be found.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn default_path() -> Option<PathBuf> {
+        if let Some(config) = std::env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+            return Some(PathBuf::from(config).join("pinenut"));
+        }
+        std::env::var_os("HOME")
+            .or_else(|| std::env::var_os("USERPROFILE"))
+            .map(|home| PathBuf::from(home).join(".config").join("pinenut"))
+    }
+
+    /// Loads the profile `name` from the default credentials file.
+    ///
+    /// # Error
+    ///
+    /// Returns an [`Error::CredentialsError`] if there is no home directory, the file isn't valid
+    /// TOML or doesn't have the profile, and an [`Error::IoError`] if it can't be read.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(name: &str) -> Result<Profile> {
+        let path = Profile::default_path()
+            .ok_or_else(|| Error::CredentialsError("no home directory to find the credentials file in".to_string()))?;
+        Profile::load_from(path, name)
+    }
+
+    /// Loads the profile `name` from the credentials file at `path`, see [`Profile::load`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_from(path: impl AsRef<Path>, name: &str) -> Result<Profile> {
+        let path = path.as_ref();
+        let content = Zeroizing::new(std::fs::read_to_string(path).map_err(Error::IoError)?);
+        Profile::parse(&content, name)
+            .map_err(|err| Error::CredentialsError(format!("{}: {}", path.display(), err)))
+    }
+
+    /// Parses the profile `name` from the content of a credentials file.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn parse(content: &str, name: &str) -> std::result::Result<Profile, String> {
+        let mut profiles: HashMap<String, Profile> = toml::from_str(content).map_err(|err| err.message().to_string())?;
+        profiles.remove(name).ok_or_else(|| format!("there is no profile named {}", name))
+    }
+}
+
+#[cfg(feature = "profiles")]
+impl CredentialsProvider for Profile {
+    fn api_key(&self) -> Result<ApiKey> {
+        Ok(self.api_key.clone())
+    }
+
+    fn environment(&self) -> Option<String> {
+        self.environment.clone()
+    }
+
+    fn control_plane(&self) -> Option<ControlPlane> {
+        self.control_plane
+    }
+
+    fn controller_url(&self) -> Option<String> {
+        self.controller_url.clone()
+    }
+}
+
+type Refresh = Box<dyn Fn() -> Result<String> + Send + Sync>;
+
+/// An api key that can be replaced while it's in use, rotating it for every [`Index`] sharing it.
+///
+/// The key is either replaced by calling [`RefreshableCredentials::rotate`], for example from a
+/// task watching a secret store, or requested again by a refresh function once it gets older
+/// than a maximum age. The refresh function runs on the path of the first request to find the key
+/// too old, which blocks until it returns, while concurrent requests wait for that refresh rather
+/// than each starting their own. Slow sources are better rotated from a task of their own. Keep
+/// an [`Arc`] to the credentials to rotate them after handing a clone of it to the [`Client`].
+///
+/// ```no_run
+/// use std::{sync::Arc, time::Duration};
+/// use pinenut::{Client, credentials::RefreshableCredentials};
+///
+/// # async fn run() -> pinenut::Result<()> {
+/// let credentials = Arc::new(RefreshableCredentials::with_refresh(Duration::from_secs(300), || {
+///     std::fs::read_to_string("/run/secrets/pinecone").map(|key| key.trim().to_string()).map_err(pinenut::Error::IoError)
+/// })?);
+/// let client = Client::builder_with_credentials(credentials.clone()).environment("us-east1-gcp").build().await?;
+/// let index = client.index("my-index");
+///
+/// // Every request of `client` and `index` uses the new key from now on.
+/// credentials.rotate("new-api-key");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Index`]: crate::Index
+/// [`Client`]: crate::Client
+pub struct RefreshableCredentials {
+    current: RwLock<(ApiKey, Instant)>,
+    refresh: Option<(Duration, Refresh)>,
+    environment: Option<String>
+}
+
+impl RefreshableCredentials {
+
+    /// Credentials only changing when rotated.
+    pub fn new(api_key: impl Into<String>) -> RefreshableCredentials {
+        RefreshableCredentials {
+            current: RwLock::new((ApiKey::new(api_key), Instant::now())),
+            refresh: None,
+            environment: None
+        }
+    }
+
+    /// Credentials requesting their key from `refresh` now and again whenever it's older than
+    /// `max_age`.
+    ///
+    /// # Error
+    ///
+    /// Returns the error of `refresh` if the first key can't be requested.
+    pub fn with_refresh<F>(max_age: Duration, refresh: F) -> Result<RefreshableCredentials>
+    where
+        F: Fn() -> Result<String> + Send + Sync + 'static
+    {
+        let api_key = refresh()?;
+        Ok(RefreshableCredentials {
+            current: RwLock::new((ApiKey::new(api_key), Instant::now())),
+            refresh: Some((max_age, Box::new(refresh))),
+            environment: None
+        })
+    }
+
+    /// Sets the environment of the legacy control plane.
+    pub fn environment(mut self, environment: impl Into<String>) -> RefreshableCredentials {
+        self.environment = Some(environment.into());
+        self
+    }
+
+    /// Replaces the api key, which is used by the next request.
+    pub fn rotate(&self, api_key: impl Into<String>) {
+        *self.current.write().unwrap() = (ApiKey::new(api_key), Instant::now());
+    }
+
+    /// Requests a new api key from the refresh function right away, doing nothing for credentials
+    /// without one.
+    pub fn refresh(&self) -> Result<()> {
+        if let Some((_, ref refresh)) = self.refresh {
+            self.rotate(refresh()?);
+        }
+        Ok(())
+    }
+}
+
+impl CredentialsProvider for RefreshableCredentials {
+    /// Returns the current key, refreshing it first if it's too old. A failed refresh returns its
+    /// error rather than the outdated key.
+    fn api_key(&self) -> Result<ApiKey> {
+        if let Some((max_age, ref refresh)) = self.refresh {
+            if self.current.read().unwrap().1.elapsed() >= max_age {
+                let mut current = self.current.write().unwrap();
+                // another request may have refreshed the key while this one waited for the lock.
+                if current.1.elapsed() >= max_age {
+                    *current = (ApiKey::new(refresh()?), Instant::now());
+                }
+                return Ok(current.0.clone());
+            }
+        }
+        Ok(self.current.read().unwrap().0.clone())
+    }
+
+    fn environment(&self) -> Option<String> {
+        self.environment.clone()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod credentials_test {
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use reqwest::{Method, StatusCode};
+
+    use super::*;
+    use crate::{Client, mock::MockPinecone, models::IndexCreateRequest, rest::stub::{Stub, StubResponse}};
+
+    const PROFILES: &str = r#"
+        [default]
+        api_key = "default-key"
+        environment = "us-east1-gcp"
+
+        [serverless]
+        api_key = "serverless-key"
+        control_plane = "global"
+        controller_url = "http://localhost:8080"
+    "#;
+
+    #[test]
+    fn test_env_credentials() {
+        std::env::set_var("PINENUT_TEST_API_KEY", "env-key");
+        std::env::set_var("PINENUT_TEST_ENV", "us-west4-gcp");
+        let credentials = EnvCredentials::with_vars("PINENUT_TEST_API_KEY", "PINENUT_TEST_ENV");
+        assert_eq!(credentials.api_key().unwrap().expose(), "env-key");
+        assert_eq!(credentials.environment().as_deref(), Some("us-west4-gcp"));
+
+        let missing = EnvCredentials::with_vars("PINENUT_TEST_MISSING_KEY", "PINENUT_TEST_MISSING_ENV");
+        assert!(matches!(missing.api_key(), Err(Error::CredentialsError(_))));
+        assert_eq!(missing.environment(), None);
+    }
+
+    #[test]
+    fn test_profiles() {
+        let default = Profile::parse(PROFILES, Profile::DEFAULT).unwrap();
+        assert_eq!(default.api_key().unwrap().expose(), "default-key");
+        assert_eq!(default.environment().as_deref(), Some("us-east1-gcp"));
+        assert_eq!(default.control_plane, None);
+
+        let serverless = Profile::parse(PROFILES, "serverless").unwrap();
+        assert_eq!(serverless.api_key.expose(), "serverless-key");
+        assert_eq!(serverless.control_plane, Some(ControlPlane::Global));
+        assert_eq!(serverless.controller_url.as_deref(), Some("http://localhost:8080"));
+
+        assert!(Profile::parse(PROFILES, "staging").unwrap_err().contains("staging"));
+        assert!(Profile::parse("[default]\nenvironment = 1", Profile::DEFAULT).is_err());
+    }
+
+    #[test]
+    fn test_load_profile_file() {
+        let path = std::env::temp_dir().join(format!("pinenut-profiles-{}", std::process::id()));
+        std::fs::write(&path, PROFILES).unwrap();
+        assert_eq!(Profile::load_from(&path, "serverless").unwrap().api_key.expose(), "serverless-key");
+        assert!(matches!(Profile::load_from(&path, "staging"), Err(Error::CredentialsError(_))));
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(Profile::load_from(&path, Profile::DEFAULT), Err(Error::IoError(_))));
+    }
+
+    #[test]
+    fn test_refresh_after_max_age() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let credentials = RefreshableCredentials::with_refresh(Duration::ZERO, move || {
+            Ok(format!("key-{}", counter.fetch_add(1, Ordering::SeqCst)))
+        }).unwrap();
+        assert_eq!(credentials.api_key().unwrap().expose(), "key-1");
+        assert_eq!(credentials.api_key().unwrap().expose(), "key-2");
+
+        let credentials = RefreshableCredentials::with_refresh(Duration::from_secs(60), || Ok("fresh".to_string())).unwrap();
+        credentials.rotate("rotated");
+        assert_eq!(credentials.api_key().unwrap().expose(), "rotated");
+        credentials.refresh().unwrap();
+        assert_eq!(credentials.api_key().unwrap().expose(), "fresh");
+    }
+
+    #[test]
+    fn test_concurrent_refresh_runs_once() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let credentials = RefreshableCredentials::with_refresh(Duration::from_millis(200), move || {
+            std::thread::sleep(Duration::from_millis(20));
+            Ok(format!("key-{}", counter.fetch_add(1, Ordering::SeqCst)))
+        }).unwrap();
+        std::thread::sleep(Duration::from_millis(250));
+        let keys: Vec<String> = std::thread::scope(|scope| {
+            let requests: Vec<_> = (0..8).map(|_| scope.spawn(|| credentials.api_key().unwrap().expose().to_string())).collect();
+            requests.into_iter().map(|request| request.join().unwrap()).collect()
+        });
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert!(keys.iter().all(|key| key == "key-1"));
+    }
+
+    #[tokio::test]
+    async fn test_profile_settings_reach_the_builder() {
+        let mock = MockPinecone::start().await;
+        let profile = Profile::parse(&format!("[default]\napi_key = \"{}\"\ncontroller_url = \"{}\"", mock.api_key(), mock.url()), Profile::DEFAULT).unwrap();
+        // builds without an environment as the controller url comes from the profile.
+        Client::builder_with_credentials(profile).build().await.unwrap();
+
+        let controller = Stub::routes(vec![
+            (Method::GET, "/indexes", StubResponse::new(StatusCode::OK, r#"{"indexes":[]}"#))
+        ]);
+        let profile = Profile::parse(&format!("[serverless]\napi_key = \"key\"\ncontrol_plane = \"global\"\ncontroller_url = \"{}\"", controller.url()), "serverless").unwrap();
+        let client = Client::builder_with_credentials(profile.clone()).build().await.unwrap();
+        assert_eq!(client.control_plane(), ControlPlane::Global);
+        assert_eq!(controller.requests()[0].path, "/indexes");
+
+        let overridden = Client::builder_with_credentials(profile).control_plane(ControlPlane::Legacy).build().await;
+        assert!(matches!(overridden, Err(Error::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_rotation_reaches_every_index() {
+        let mock = MockPinecone::start().await;
+        let credentials = Arc::new(RefreshableCredentials::new(mock.api_key()));
+        let client = Client::builder_with_credentials(credentials.clone())
+            .controller_url(mock.url())
+            .build()
+            .await
+            .unwrap();
+        client.create_index(IndexCreateRequest::builder("pinenut", 4).build().unwrap()).await.unwrap();
+        let index = client.index("pinenut");
+        index.describe_stats().await.unwrap();
+
+        credentials.rotate("revoked-key");
+        assert!(matches!(index.describe_stats().await, Err(Error::Unauthorized(_))));
+        assert!(matches!(client.list_indexes().await, Err(Error::Unauthorized(_))));
+
+        credentials.rotate(mock.api_key());
+        index.clone().describe_stats().await.unwrap();
+    }
+
+    const SECRET: &str = "pinenut-secret-key";
+
+    /// Asserts that neither the `Debug` nor the `Display` output of `value` holds the secret key.
+    fn assert_redacted(value: &(impl fmt::Debug + fmt::Display)) {
+        assert!(!format!("{:?}", value).contains(SECRET), "{:?}", value);
+        assert!(!format!("{}", value).contains(SECRET), "{}", value);
+    }
+
+    #[test]
+    fn test_api_key_redaction() {
+        let key = ApiKey::new(SECRET);
+        assert_redacted(&key);
+        assert_eq!(key.expose(), SECRET);
+
+        let profile = Profile::parse(&format!("[default]\napi_key = \"{}\"", SECRET), Profile::DEFAULT).unwrap();
+        assert!(!format!("{:?}", profile).contains(SECRET));
+        assert!(!format!("{:?}", Client::builder(SECRET)).contains(SECRET));
+    }
+
+    /// Records the `Debug` output of every request.
+    struct Snoop(Arc<std::sync::Mutex<Vec<String>>>);
+
+    #[async_trait::async_trait]
+    impl crate::Middleware for Snoop {
+        async fn before_send(&self, _: &crate::middleware::RequestContext<'_>, request: &mut reqwest::Request) -> Result<()> {
+            self.0.lock().unwrap().push(format!("{:?}", request));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_errors_do_not_leak_api_key() {
+        let mock = MockPinecone::with_api_key("another-key").await;
+        let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let unauthorized = Client::builder(SECRET)
+            .controller_url(mock.url())
+            .middleware(Snoop(requests.clone()))
+            .build()
+            .await
+            .err().unwrap();
+        assert!(matches!(unauthorized, Error::Unauthorized(_)));
+        assert_redacted(&unauthorized);
+        let requests = requests.lock().unwrap().clone();
+        assert!(requests[0].contains("api-key"));
+        assert!(requests.iter().all(|request| !request.contains(SECRET)));
+
+        let unreachable = Client::builder(SECRET)
+            .controller_url("http://127.0.0.1:1")
+            .retry_policy(crate::RetryPolicy::none())
+            .build()
+            .await
+            .err().unwrap();
+        assert!(matches!(unreachable, Error::ReqwestError(_)));
+        assert_redacted(&unreachable);
+
+        let invalid = format!("{}\n", SECRET);
+        let invalid = Client::builder(invalid.as_str()).controller_url(mock.url()).build().await.err().unwrap();
+        assert!(matches!(invalid, Error::ArgumentError{..}));
+        assert_redacted(&invalid);
+    }
+}
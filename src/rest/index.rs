@@ -1,11 +1,20 @@
+use std::{future::Future, sync::{Arc, RwLock}};
+
 use reqwest::{StatusCode, Method};
 use serde_json::Value;
-use crate::{Result, rest::{try_pinecone_request_json, try_pinecone_request_text}, models::{ConfigureIndexRequest, UpdateRequest, FetchRequest, FetchResponse, QueryRequest, QueryResponse, ClientInfo}}; 
+use futures::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt, Stream, StreamExt, TryStreamExt, stream};
+use crate::{Error, Result, rest::{Operation, try_pinecone_request_json, try_pinecone_request_text}, models::{ConfigureIndexRequest, UpdateRequest, DeleteRequest, FetchRequest, FetchResponse, ListOptions, ListRequest, ListResponse, QueryRequest, QueryResponse}}; 
 
 use super::{
     Connection,
     Credentials,
-    models::{VectorRequest, Vector, IndexStats, UpsertResponse, IndexDescription, Metric},
+    ConnectionConfig,
+    batch::{self, BatchOptions, BatchUpsertResponse, BatchFailure},
+    transfer::{ExportOptions, Progress},
+    ControlPlane,
+    WaitOptions,
+    wait::{self, Poll},
+    models::{VectorRequest, Vector, IndexStats, DescribeIndexStatsRequest, Filter, UpsertResponse, IndexDescription, Metric, GlobalIndexDescription, GlobalConfigureIndexRequest, GlobalConfigureSpec, DescribeStatusState},
 };
 
 impl From<Metric> for String {
@@ -15,75 +24,177 @@ impl From<Metric> for String {
 }
 
 /// Represents a connection to an Index. All Index specific operations are on this type.
+///
+/// An [`Index`] shares the connection pool of the [`Client`] it was created from, so creating and
+/// cloning index handles is cheap. Every operation takes `&self` and the cached description, stats
+/// and host are shared between clones, so a single [`Index`] can be used from many tasks at once,
+/// either cloned or behind an [`Arc`].
+///
+/// [`Client`]: crate::Client
+#[derive(Clone)]
 pub struct Index {
     client: reqwest::Client,
     name: String,
     creds: Credentials,
-    description: Option<IndexDescription>,
-    client_info: ClientInfo,
-    stats: Option<IndexStats> 
+    description: Arc<RwLock<Option<IndexDescription>>>,
+    /// The data plane host, shared between clones so that it is only described once.
+    host: Arc<RwLock<Option<String>>>,
+    stats: Arc<RwLock<Option<IndexStats>>>,
+    config: ConnectionConfig
 }
 
 impl Index {
 
-    pub(crate) fn new<C>(con: &C, name: impl Into<String>, client_info: &ClientInfo) -> Index 
+    pub(crate) fn new<C>(con: &C, name: impl Into<String>, host: Option<String>) -> Index 
     where
         C: Connection
     {
         Index {
-            client: reqwest::Client::new(),
+            client: con.client().clone(),
             name: name.into(),
             creds: con.credentials().clone(), 
-            client_info: client_info.clone(),
-            description: None,
-            stats: None
+            description: Arc::default(),
+            host: Arc::new(RwLock::new(host)),
+            stats: Arc::default(),
+            config: con.config().clone()
         }
     }
 
     /// Creates a brand new IndexDescription from pinecone. If successfull this will be
-    /// cached.
+    /// cached, alongside the host used for data plane requests.
     ///
     /// This method can also be used as a kind of Validation for you're credentials / Index. If it
     /// returns an Ok value the Index exists and if it returns an Error it likely does not.
-    pub async fn describe(&mut self)  -> Result<&IndexDescription> {
-        let name = self.name.clone();
-        self.description = Some(try_pinecone_request_json::<Index, String, IndexDescription>(self, Method::GET, StatusCode::OK, None::<String>, format!("/databases/{}", name), None).await?);
-        Ok(self.description().unwrap())
+    pub async fn describe(&self) -> Result<IndexDescription> {
+        let description = self.fetch_description().await?;
+        *self.description.write().unwrap() = Some(description.clone());
+        Ok(description)
+    }
+
+    /// Requests the [`IndexDescription`] and caches its host, without caching the description
+    /// itself.
+    pub(crate) async fn fetch_description(&self) -> Result<IndexDescription> {
+        let path = format!("{}/{}", self.config.control_plane.indexes_path(), self.name);
+        let description = match self.config.control_plane {
+            ControlPlane::Legacy => try_pinecone_request_json::<Index, String, IndexDescription>(self, Operation::new("describe_index"), Method::GET, StatusCode::OK, None::<String>, path, None).await?,
+            ControlPlane::Global => try_pinecone_request_json::<Index, String, GlobalIndexDescription>(self, Operation::new("describe_index"), Method::GET, StatusCode::OK, None::<String>, path, None).await?.into()
+        };
+        if let Some(ref host) = description.status.host {
+            *self.host.write().unwrap() = Some(host.clone());
+        }
+        Ok(description)
+    }
+
+    /// Waits until the index is ready, polling its description with the backoff and deadline of
+    /// `options`. This should be used after [`Client::create_index`] or [`Index::configure`] as the
+    /// index won't accept operations until it's ready. The final description is cached.
+    ///
+    /// # Error
+    ///
+    /// Returns an [`Error::InitializationFailed`] if the index fails to initialize and an
+    /// [`Error::WaitTimeout`] if it isn't ready within [`WaitOptions::timeout`].
+    ///
+    /// [`Client::create_index`]: crate::Client::create_index
+    pub async fn wait_until_ready(&self, options: WaitOptions) -> Result<IndexDescription> {
+        let description = wait::poll(&self.name, &options, || async {
+            let description = self.fetch_description().await?;
+            match description.status.state {
+                DescribeStatusState::InitializationFailed => Err(Error::InitializationFailed{name: self.name.clone()}),
+                DescribeStatusState::Ready if description.status.ready => Ok(Poll::Done(description)),
+                ref state => Ok(Poll::Pending(state.to_string()))
+            }
+        }).await?;
+        *self.description.write().unwrap() = Some(description.clone());
+        Ok(description)
+    }
+
+    /// Adds a [`Middleware`] run around every request of this [`Index`], after the middlewares of
+    /// the [`Client`] it was created from. Clones of the index made afterwards share it, the
+    /// client and other indexes don't.
+    ///
+    /// [`Middleware`]: crate::Middleware
+    /// [`Client`]: crate::Client
+    pub fn with_middleware(mut self, middleware: impl crate::Middleware + 'static) -> Index {
+        self.config.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// The name of the index.
+    pub fn name(&self) -> &str {
+        &self.name
     }
 
-    /// Returns the cached [`IndexDescription`]
-    pub fn description(&self) -> Option<&IndexDescription> {
-        self.description.as_ref()
+    /// Returns a copy of the cached [`IndexDescription`]
+    pub fn description(&self) -> Option<IndexDescription> {
+        self.description.read().unwrap().clone()
     }
 
     /// Attempts to return the cached [`IndexDescription`], if unsuccessfull it will make a request
     /// and then return the cached description
-    pub async fn cached_then_normal_describe(&mut self) -> Result<&IndexDescription>{
-        if let Some(ref desc) = self.description {
-            return Ok(desc);
+    pub async fn cached_then_normal_describe(&self) -> Result<IndexDescription>{
+        if let Some(description) = self.description() {
+            return Ok(description);
         }
         self.describe().await
     }
 
 
-    /// Returns the url for api requests if it's been cached, this is typically stored in
-    /// [`IndexDescription`]
-    pub fn url(&self) -> String {
-        format!("https://{}-{}.svc.{}.pinecone.io", self.name, self.client_info.project_name, self.creds.environment)
+    /// Returns the url for data plane requests if it's known, either from the data plane url of
+    /// the [`ClientBuilder`], the host given to [`Client::index_by_host`] or the host of a
+    /// previously requested [`IndexDescription`].
+    ///
+    /// # Error
+    ///
+    /// This function will return an [`Error::URLNotAvailable`] if the url isn't known yet, data
+    /// plane operations will describe the index to find it when this is the case.
+    ///
+    /// [`ClientBuilder`]: crate::ClientBuilder
+    /// [`Client::index_by_host`]: crate::Client::index_by_host
+    pub fn url(&self) -> Result<String> {
+        if let Some(ref url) = self.config.data_plane_url {
+            return Ok(url.replace("{index}", &self.name));
+        }
+        match *self.host.read().unwrap() {
+            Some(ref host) if host.starts_with("http://") || host.starts_with("https://") => Ok(host.trim_end_matches('/').to_string()),
+            Some(ref host) => Ok(format!("https://{}", host.trim_end_matches('/'))),
+            None => Err(Error::URLNotAvailable)
+        }
+    }
+
+    /// Returns the data plane url, describing the index if it isn't known yet.
+    pub(crate) async fn resolve_url(&self) -> Result<String> {
+        if let Ok(url) = self.url() {
+            return Ok(url);
+        }
+        self.fetch_description().await?;
+        self.url()
     }
 
     /// Grabs the latest [`IndexStats`] from pinecone and caches it if successfull.
     ///
     /// To grab the cached version use [`stats`]
-    pub async fn describe_stats(&mut self) -> Result<&IndexStats> {
-        self.stats = Some(try_pinecone_request_json::<Index, String, IndexStats>(self, Method::GET, StatusCode::OK, Some(self.url()), "/describe_index_stats", None).await?);
-        Ok(self.stats().unwrap())
+    pub async fn describe_stats(&self) -> Result<IndexStats> {
+        let stats = self.fetch_stats().await?;
+        *self.stats.write().unwrap() = Some(stats.clone());
+        Ok(stats)
+    }
+
+    /// Requests the [`IndexStats`] without caching them.
+    pub(crate) async fn fetch_stats(&self) -> Result<IndexStats> {
+        try_pinecone_request_json::<Index, String, IndexStats>(self, Operation::new("describe_index_stats"), Method::GET, StatusCode::OK, Some(self.resolve_url().await?), "/describe_index_stats", None).await
     }
 
+    /// Requests the [`IndexStats`] of only the vectors matching `filter`, such as the vectors of a
+    /// single tenant. Unlike [`Index::describe_stats`] the result isn't cached, so the cached
+    /// stats remain those of the whole index.
+    pub async fn describe_stats_filtered(&self, filter: Filter) -> Result<IndexStats> {
+        let request = DescribeIndexStatsRequest{filter: Some(filter)};
+        try_pinecone_request_json::<Index, DescribeIndexStatsRequest, IndexStats>(self, Operation::new("describe_index_stats"), Method::POST, StatusCode::OK, Some(self.resolve_url().await?), "/describe_index_stats", Some(&request)).await
+    }
 
-    /// Returns the cached [`IndexStats`].
-    pub fn stats(&self) -> Option<&IndexStats> {
-        self.stats.as_ref()
+    /// Returns a copy of the cached [`IndexStats`].
+    pub fn stats(&self) -> Option<IndexStats> {
+        self.stats.read().unwrap().clone()
     }
 
     /// Upsert takes in a [`Vec<Vector>`] and attempts to upsert / upload it to pinecone. It will
@@ -93,45 +204,301 @@ impl Index {
             namespace,
             vectors
         };
-        try_pinecone_request_json::<Index, VectorRequest, UpsertResponse>(self, Method::POST, StatusCode::OK, Some(self.url()), "/vectors/upsert", Some(&upsert)).await
+        try_pinecone_request_json::<Index, VectorRequest, UpsertResponse>(self, Operation::new("upsert").namespace(Some(&upsert.namespace)).vectors(upsert.vectors.len()), Method::POST, StatusCode::OK, Some(self.resolve_url().await?), "/vectors/upsert", Some(&upsert)).await
+    }
+
+    /// Upserts any number of vectors by splitting them into chunks that stay within Pinecone's
+    /// request limits, described by [`BatchOptions`], and sending up to
+    /// [`BatchOptions::concurrency`] chunks at a time.
+    ///
+    /// A failed chunk does not stop the others from being sent. The returned
+    /// [`BatchUpsertResponse`] holds the combined [`UpsertResponse`] of the successfull chunks
+    /// alongside every failed chunk, so partial success should be checked for with
+    /// [`BatchUpsertResponse::is_complete`].
+    ///
+    /// # Error
+    ///
+    /// This function only errors if `options` is invalid or the data plane url can't be found,
+    /// request failures are reported within the [`BatchUpsertResponse`].
+    pub async fn upsert_batched(&self, namespace: String, vectors: Vec<Vector>, options: BatchOptions) -> Result<BatchUpsertResponse> {
+        let chunks = batch::chunk_vectors(&namespace, vectors, &options)?;
+        // resolved once up front so that concurrent chunks don't each describe the index.
+        self.resolve_url().await?;
+        Ok(self.upsert_chunks(&namespace, chunks, 0, options.concurrency).await)
+    }
+
+    /// Upserts `chunks` with up to `concurrency` requests at a time, numbering the chunks from
+    /// `first_chunk` on.
+    async fn upsert_chunks(&self, namespace: &str, chunks: Vec<Vec<Vector>>, first_chunk: usize, concurrency: usize) -> BatchUpsertResponse {
+        let results = stream::iter(chunks.into_iter().enumerate())
+            .map(|(chunk, vectors)| {
+                let namespace = namespace.to_string();
+                async move {
+                    let ids: Vec<String> = vectors.iter().map(|v| v.id.clone()).collect();
+                    self.upsert(namespace, vectors).await
+                        .map_err(|error| BatchFailure{chunk: first_chunk + chunk, ids, error})
+                }
+            })
+            .buffer_unordered(concurrency)
+            .collect::<Vec<_>>()
+            .await;
+
+        let mut batched = BatchUpsertResponse::default();
+        for result in results {
+            match result {
+                Ok(resp) => batched.response.upserted_count += resp.upserted_count,
+                Err(failure) => batched.failures.push(failure)
+            }
+        }
+        batched.failures.sort_by_key(|f| f.chunk);
+        batched
+    }
+
+    /// Writes every vector of `namespace` to `writer` as [JSON Lines](https://jsonlines.org), one
+    /// [`Vector`] per line, returning the number of vectors written. Ids are listed with
+    /// [`Index::list_ids`] and their vectors requested [`ExportOptions::fetch_size`] at a time
+    /// with [`Index::fetch`], `progress` being called after every fetch.
+    ///
+    /// Vectors upserted or deleted while the export runs may or may not be part of it.
+    ///
+    /// The writer is a [`futures::io::AsyncWrite`] so that writing doesn't block the runtime, a
+    /// tokio file can be used through the `compat_write` adapter of `tokio-util`.
+    ///
+    /// ```no_run
+    /// use pinenut::{Index, transfer::ExportOptions};
+    ///
+    /// # async fn run(index: Index) -> pinenut::Result<()> {
+    /// let mut exported = Vec::new();
+    /// let count = index.export_jsonl("tenant-a", &mut exported, ExportOptions::default(), |progress| {
+    ///     println!("{} of {:?} vectors exported", progress.vectors, progress.total);
+    /// }).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Error
+    ///
+    /// Returns an [`Error::ArgumentError`] if [`ExportOptions::fetch_size`] is 0 and an
+    /// [`Error::IoError`] if writing fails. The export stops at the first error.
+    pub async fn export_jsonl<W: AsyncWrite + Unpin>(&self, namespace: impl Into<String>, mut writer: W, options: ExportOptions, mut progress: impl FnMut(Progress)) -> Result<usize> {
+        if options.fetch_size == 0 {
+            return Err(Error::ArgumentError {
+                name: "fetch_size".to_string(),
+                found: "0".to_string(),
+                expected: "a fetch size greater than 0".to_string()
+            });
+        }
+        let namespace = namespace.into();
+        let total = self.fetch_stats().await?.namespaces.get(&namespace).map_or(0, |n| n.vector_count);
+        let list = ListOptions{page_size: options.page_size, ..Default::default()};
+        let mut ids = Box::pin(self.list_ids(namespace.clone(), None, list));
+        let mut chunk = Vec::with_capacity(options.fetch_size);
+        let mut written = 0;
+        loop {
+            let id = ids.try_next().await?;
+            let done = id.is_none();
+            chunk.extend(id);
+            if chunk.len() == options.fetch_size || (done && !chunk.is_empty()) {
+                let request = FetchRequest{ids: std::mem::take(&mut chunk), namespace: Some(namespace.clone())};
+                let mut lines = Vec::new();
+                for vector in self.fetch(request).await?.vectors.into_values() {
+                    serde_json::to_writer(&mut lines, &vector).map_err(Error::JsonError)?;
+                    lines.push(b'\n');
+                    written += 1;
+                }
+                writer.write_all(&lines).await.map_err(Error::IoError)?;
+                progress(Progress{vectors: written, total: Some(total.max(written))});
+            }
+            if done {
+                break;
+            }
+        }
+        writer.flush().await.map_err(Error::IoError)?;
+        Ok(written)
+    }
+
+    /// Upserts every [`Vector`] of a [JSON Lines](https://jsonlines.org) file, such as one
+    /// written by [`Index::export_jsonl`], into `namespace`. The file is read as it is upserted,
+    /// enough vectors at a time to send [`BatchOptions::concurrency`] chunks at once, and
+    /// `progress` is called after every round of chunks. Blank lines are skipped. Like the writer
+    /// of [`Index::export_jsonl`] the reader is asynchronous, a [`futures::io::AsyncBufRead`].
+    ///
+    /// Like [`Index::upsert_batched`] a failed chunk does not stop the import, failures are
+    /// reported within the returned [`BatchUpsertResponse`] with chunks numbered from the start of
+    /// the file.
+    ///
+    /// # Error
+    ///
+    /// Returns an [`Error::ArgumentError`] naming the line of the first line that isn't a json
+    /// encoded [`Vector`] and an [`Error::IoError`] if reading fails. Vectors read before the
+    /// error are already upserted.
+    pub async fn import_jsonl<R: AsyncBufRead + Unpin>(&self, namespace: impl Into<String>, reader: R, options: BatchOptions, mut progress: impl FnMut(Progress)) -> Result<BatchUpsertResponse> {
+        let namespace = namespace.into();
+        // checks the options before anything is read.
+        batch::chunk_vectors(&namespace, Vec::new(), &options)?;
+        self.resolve_url().await?;
+        let round = options.max_vectors.saturating_mul(options.concurrency);
+        let mut lines = reader.lines().enumerate();
+        let mut imported = BatchUpsertResponse::default();
+        let mut chunks_sent = 0;
+        let mut read = 0;
+        loop {
+            let mut vectors = Vec::new();
+            while let Some((number, line)) = lines.next().await {
+                let line = line.map_err(Error::IoError)?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let vector: Vector = serde_json::from_str(&line).map_err(|err| Error::ArgumentError {
+                    name: format!("line {}", number + 1),
+                    found: err.to_string(),
+                    expected: "a json encoded Vector".to_string()
+                })?;
+                vectors.push(vector);
+                if vectors.len() >= round {
+                    break;
+                }
+            }
+            if vectors.is_empty() {
+                break;
+            }
+            read += vectors.len();
+            let chunks = batch::chunk_vectors(&namespace, vectors, &options)?;
+            let count = chunks.len();
+            let sent = self.upsert_chunks(&namespace, chunks, chunks_sent, options.concurrency).await;
+            chunks_sent += count;
+            imported.response.upserted_count += sent.response.upserted_count;
+            imported.failures.extend(sent.failures);
+            progress(Progress{vectors: read, total: None});
+        }
+        Ok(imported)
     }
 
     /// Delete will attempt to delete the current Index and return the associated Message returned
     /// by Pinecone when successfull. This will error if the Index does not exist.
-    pub async fn delete(self) -> Result<String> {
-        try_pinecone_request_text::<Index, String>(&self, Method::DELETE, StatusCode::ACCEPTED, None::<String>, format!("/databases/{}", self.name), None).await
+    ///
+    /// The index is deleted in the background, [`Client::wait_until_index_deleted`] can be used
+    /// to wait until it's gone.
+    ///
+    /// [`Client::wait_until_index_deleted`]: crate::Client::wait_until_index_deleted
+    pub async fn delete(&self) -> Result<String> {
+        try_pinecone_request_text::<Index, String>(self, Operation::new("delete_index"), Method::DELETE, StatusCode::ACCEPTED, None::<String>, format!("{}/{}", self.config.control_plane.indexes_path(), self.name), None).await
     }
 
     /// Configures the current index, specifically [`replicas`] and [`pod_type`] settings. More can
-    /// be found at [Pinecone](https://docs.pinecone.io/reference/configure_index)
+    /// be found at [Pinecone](https://docs.pinecone.io/reference/configure_index). Only pod based
+    /// indexes can be configured. The change is applied in the background, see
+    /// [`Index::wait_until_ready`].
     pub async fn configure(&self, replicas: usize, pod_type: String) -> Result<String> {
         let p = ConfigureIndexRequest{
             replicas,
             pod_type
         };
-        try_pinecone_request_text::<Index, ConfigureIndexRequest>(self, Method::PATCH, StatusCode::ACCEPTED, None::<String>, format!("/databases/{}", self.name), Some(&p)).await
+        let path = format!("{}/{}", self.config.control_plane.indexes_path(), self.name);
+        match self.config.control_plane {
+            ControlPlane::Legacy => try_pinecone_request_text::<Index, ConfigureIndexRequest>(self, Operation::new("configure_index"), Method::PATCH, StatusCode::ACCEPTED, None::<String>, path, Some(&p)).await,
+            ControlPlane::Global => {
+                let p = GlobalConfigureIndexRequest{spec: GlobalConfigureSpec{pod: p}};
+                try_pinecone_request_text::<Index, GlobalConfigureIndexRequest>(self, Operation::new("configure_index"), Method::PATCH, StatusCode::ACCEPTED, None::<String>, path, Some(&p)).await
+            }
+        }
     }
 
     /// Updates a vector within the index. The return type of the Ok() value should be ignored as
     /// this method returns an empty json object.
-    pub async fn update(&mut self, request: UpdateRequest) -> Result<Value> {
-        try_pinecone_request_json::<Index, UpdateRequest, Value>(self, Method::POST, StatusCode::OK, Some(self.url()), "/vectors/update", Some(&request)).await
+    pub async fn update(&self, request: UpdateRequest) -> Result<Value> {
+        try_pinecone_request_json::<Index, UpdateRequest, Value>(self, Operation::new("update").namespace(request.namespace.as_deref()), Method::POST, StatusCode::OK, Some(self.resolve_url().await?), "/vectors/update", Some(&request)).await
+    }
+
+    /// Deletes vectors from the index, either by id, every vector within a namespace or every
+    /// vector matching a metadata filter. Like [`Index::update`] the Ok() value is an empty json
+    /// object and should be ignored.
+    ///
+    /// # Error
+    ///
+    /// This function will return an [`Error::ArgumentError`] without making a request if the
+    /// [`DeleteRequest`] doesn't target exactly one of ids, delete_all or filter.
+    ///
+    /// [`Error::ArgumentError`]: crate::Error::ArgumentError
+    pub async fn delete_vectors(&self, request: DeleteRequest) -> Result<Value> {
+        request.validate()?;
+        try_pinecone_request_json::<Index, DeleteRequest, Value>(self, Operation::new("delete").namespace(request.namespace.as_deref()), Method::POST, StatusCode::OK, Some(self.resolve_url().await?), "/vectors/delete", Some(&request)).await
     }
 
     /// Looksup and returns vectors, by ID, from a single namespace. The returned vectors
     /// include the vector data and/or metadata.
-    pub async fn fetch(&mut self, request: FetchRequest) -> Result<FetchResponse> {
-        let url = request.url(self.url());
-        try_pinecone_request_json::<Index, String, FetchResponse>(self, Method::GET, StatusCode::OK, Some(url), "", None).await
+    pub async fn fetch(&self, request: FetchRequest) -> Result<FetchResponse> {
+        let namespace = request.namespace.clone();
+        let url = request.url(self.resolve_url().await?)?;
+        try_pinecone_request_json::<Index, String, FetchResponse>(self, Operation::new("fetch").namespace(namespace.as_deref()), Method::GET, StatusCode::OK, Some(url), "", None).await
+    }
+
+    /// Lists a single page of the vector ids within a namespace. [`Index::list_ids`] follows the
+    /// pages instead, this is useful to keep track of [`ListResponse::next_token`] and resume a
+    /// listing later on. Listing is only supported by serverless indexes.
+    ///
+    /// # Error
+    ///
+    /// Returns an [`Error::ArgumentError`] without making a request if [`ListRequest::limit`] is 0.
+    pub async fn list_page(&self, request: ListRequest) -> Result<ListResponse> {
+        request.validate()?;
+        let url = request.url(self.resolve_url().await?)?;
+        try_pinecone_request_json::<Index, String, ListResponse>(self, Operation::new("list").namespace(request.namespace.as_deref()), Method::GET, StatusCode::OK, Some(url), "", None).await
+    }
+
+    /// Streams the ids of every vector within `namespace` starting with `prefix`, requesting them
+    /// a page at a time as the stream is consumed. The stream ends after the last page or after
+    /// the first error.
+    ///
+    /// A listing can be resumed from a token saved from [`Index::list_page`] through
+    /// [`ListOptions::pagination_token`].
+    ///
+    /// ```no_run
+    /// use futures::TryStreamExt;
+    /// use pinenut::{Index, models::ListOptions};
+    ///
+    /// # async fn run(index: Index) -> pinenut::Result<()> {
+    /// let options = ListOptions{page_size: Some(50), ..Default::default()};
+    /// let ids: Vec<String> = index.list_ids("tenant-a", Some("doc1#".to_string()), options).try_collect().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn list_ids(&self, namespace: impl Into<String>, prefix: Option<String>, options: ListOptions) -> impl Stream<Item = Result<String>> + '_ {
+        paginate(ListRequest::first(namespace.into(), prefix, options), move |request| self.list_page(request))
     }
 
     /// Searches a namespace using a query vector. it retrieves the ids of the most similar items
     /// in a namespace, alogn with their similarity scores.
-    pub async fn query(&mut self, request: QueryRequest) -> Result<QueryResponse> {
-        try_pinecone_request_json::<Index, QueryRequest, QueryResponse>(self, Method::POST, StatusCode::OK, Some(self.url()), "/query", Some(&request)).await
+    pub async fn query(&self, request: QueryRequest) -> Result<QueryResponse> {
+        try_pinecone_request_json::<Index, QueryRequest, QueryResponse>(self, Operation::new("query").namespace(request.namespace.as_deref()).top_k(request.top_k), Method::POST, StatusCode::OK, Some(self.resolve_url().await?), "/query", Some(&request)).await
     }
 }
 
+/// Streams the ids of every page, starting with `request`, requesting the next page with
+/// `list_page` only once the ids of the previous one are consumed.
+pub(crate) fn paginate<'a, F, Fut>(request: ListRequest, list_page: F) -> impl Stream<Item = Result<String>> + 'a
+where
+    F: Fn(ListRequest) -> Fut + 'a,
+    Fut: Future<Output = Result<ListResponse>> + 'a
+{
+    stream::try_unfold(Some(request), move |request| {
+        let page = request.clone().map(&list_page);
+        async move {
+            let (mut request, page) = match (request, page) {
+                (Some(request), Some(page)) => (request, page.await?),
+                _ => return Ok(None)
+            };
+            let next = page.next_token().map(|token| {
+                request.pagination_token = Some(token.to_string());
+                request
+            });
+            Ok(Some((page.vectors, next)))
+        }
+    })
+    .map_ok(|ids| stream::iter(ids.into_iter().map(|item| Ok(item.id))))
+    .try_flatten()
+}
+
 impl Connection for Index {
     fn client(&self) -> &reqwest::Client {
         &self.client
@@ -139,31 +506,71 @@ impl Connection for Index {
     fn credentials(&self) -> &Credentials {
         &self.creds
     }
+    fn config(&self) -> &ConnectionConfig {
+        &self.config
+    }
+    fn index_name(&self) -> Option<&str> {
+        Some(&self.name)
+    }
 }
 #[cfg(test)]
 mod index_tests {
 
     use super::*;
+    use crate::{ControlPlane, Error, rest::client::unconnected};
+
     #[cfg(target_arch="wasm32")]
     use wasm_bindgen_test::*;
-    use crate::{Client, Error};
 
-    async fn create_client() -> Client {
-        Client::new(
-            env!("PINECONE_API_KEY"),
-            env!("PINECONE_ENV")
-        ).await.unwrap()
+    #[cfg(not(target_arch="wasm32"))]
+    use crate::{Client, mock::MockPinecone, models::{IndexCreateRequest, Filter, SparseValues}};
+
+    /// Connects to a new [`MockPinecone`] holding the index `pinenut`, the mock has to be kept
+    /// alive alongside the client.
+    #[cfg(not(target_arch="wasm32"))]
+    async fn create_client() -> (MockPinecone, Client) {
+        let mock = MockPinecone::start().await;
+        let client = mock.client().await.unwrap();
+        client.create_index(IndexCreateRequest::builder("pinenut", 4).build().unwrap()).await.unwrap();
+        (mock, client)
+    }
+
+    #[cfg(not(target_arch="wasm32"))]
+    use crate::rest::{memory::vector, stub::{Stub, StubResponse}};
+
+    #[cfg(not(target_arch="wasm32"))]
+    const STUB_STATS: &str = r#"{"namespaces":{},"dimension":4,"indexFullness":0,"totalVectorCount":0}"#;
+
+    /// Starts a controller stub describing the index `local` with the given host.
+    #[cfg(not(target_arch="wasm32"))]
+    async fn stub_client(host: Option<String>) -> (Stub, Client) {
+        let host = match host {
+            Some(host) => format!("\"{}\"", host),
+            None => "null".to_string()
+        };
+        let description = format!(r#"{{"database":{{"name":"local","dimension":4,"metric":"cosine","replicas":1,"shards":1,"pods":1,"pod_type":"p1.x1"}},"status":{{"waiting":[],"crashed":[],"host":{},"port":433,"state":"Ready","ready":true}}}}"#, host);
+        let controller = Stub::routes(vec![
+            (Method::GET, "/actions/whoami", StubResponse::new(StatusCode::OK, r#"{"project_name":"stubbed","user_label":null,"user_name":null}"#)),
+            (Method::GET, "/databases/local", StubResponse::new(StatusCode::OK, description))
+        ]);
+        let client = Client::builder("stub-key")
+            .controller_url(controller.url())
+            .build()
+            .await
+            .unwrap();
+        (controller, client)
     }
 
+    #[cfg(not(target_arch="wasm32"))]
     async fn create_index(con: &Client) -> Index {
-        Index::new(con, env!("PINECONE_INDEX_NAME"), con.info())
+        Index::new(con, "pinenut", None)
     }
 
-    #[cfg_attr(not(target_arch="wasm32"), tokio::test)]
-    #[cfg_attr(target_arch="wasm32", wasm_bindgen_test)]
+    #[cfg(not(target_arch="wasm32"))]
+    #[tokio::test]
     async fn test_upsert() {
-        let client = create_client().await;
-        let mut index = create_index(&client).await;
+        let (_mock, client) = create_client().await;
+        let index = create_index(&client).await;
         if let Err(err) = index.cached_then_normal_describe().await {
             panic!("Unable to get dimension of index: {:?}", err);
         }
@@ -175,108 +582,334 @@ mod index_tests {
             metadata: None
         };
         match index.upsert(String::from("halfbaked"), vec![vec]).await {
-            Ok(_) => assert!(true),
+            Ok(resp) => assert_eq!(resp.upserted_count, 1),
             Err(err) => panic!("unable to upsert: {:?}", err)
         }
     }
 
-    #[cfg_attr(not(target_arch="wasm32"), tokio::test)]
-    #[cfg_attr(target_arch="wasm32", wasm_bindgen_test)]
-    async fn test_describe() {
-        let client = create_client().await;
-        let mut index = create_index(&client).await;
-        match index.describe().await {
-            Ok(_) => assert!(true),
-            Err(err) => panic!("failed to get description: {:?}", err)
-
+    #[cfg(not(target_arch="wasm32"))]
+    #[tokio::test]
+    async fn test_upsert_batched() {
+        let (_mock, client) = create_client().await;
+        let index = create_index(&client).await;
+        if let Err(err) = index.cached_then_normal_describe().await {
+            panic!("Unable to get dimension of index: {:?}", err);
+        }
+        let dimension = index.description().unwrap().database.dimension;
+        let vectors = (0..25).map(|i| Vector{
+            id: format!("batched-{}", i),
+            values: vec![0.5; dimension],
+            sparse_values: None,
+            metadata: None
+        }).collect();
+        let options = BatchOptions{max_vectors: 10, concurrency: 2, ..Default::default()};
+        match index.upsert_batched(String::from("halfbaked"), vectors, options).await {
+            Ok(resp) => {
+                assert!(resp.is_complete(), "failed chunks: {:?}", resp.failures);
+                assert_eq!(resp.response.upserted_count, 25);
+            },
+            Err(err) => panic!("unable to upsert: {:?}", err)
         }
+        assert_eq!(index.describe_stats().await.unwrap().namespaces["halfbaked"].vector_count, 25);
+    }
+
+    #[cfg(not(target_arch="wasm32"))]
+    #[tokio::test]
+    async fn test_url_from_description() {
+        let data_plane = Stub::routes(vec![
+            (Method::GET, "/describe_index_stats", StubResponse::new(StatusCode::OK, STUB_STATS))
+        ]);
+        let (controller, client) = stub_client(Some(data_plane.url())).await;
+        let index = client.index("local");
+        assert!(matches!(index.url(), Err(Error::URLNotAvailable)));
+
+        // the first data plane request describes the index, following ones reuse its host.
+        index.clone().describe_stats().await.unwrap();
+        index.clone().describe_stats().await.unwrap();
+        assert_eq!(index.url().unwrap(), data_plane.url());
+        assert_eq!(controller.requests().iter().filter(|r| r.path == "/databases/local").count(), 1);
+        assert_eq!(data_plane.hits(), 2);
+    }
+
+    #[cfg(not(target_arch="wasm32"))]
+    #[tokio::test]
+    async fn test_index_by_host() {
+        let data_plane = Stub::routes(vec![
+            (Method::GET, "/describe_index_stats", StubResponse::new(StatusCode::OK, STUB_STATS))
+        ]);
+        let (controller, client) = stub_client(None).await;
+        let index = client.index_by_host("local", data_plane.url());
+        assert_eq!(index.url().unwrap(), data_plane.url());
+        index.describe_stats().await.unwrap();
+        assert_eq!(controller.hits(), 1, "only whoami should reach the controller");
     }
 
     #[cfg_attr(not(target_arch="wasm32"), tokio::test)]
     #[cfg_attr(target_arch="wasm32", wasm_bindgen_test)]
+    async fn test_index_by_host_adds_scheme() {
+        let client = unconnected(ControlPlane::Legacy);
+        let index = client.index_by_host("local", "local-abc.svc.env.pinecone.io");
+        assert_eq!(index.url().unwrap(), "https://local-abc.svc.env.pinecone.io");
+        let index = client.index_by_host("local", "http://127.0.0.1:5080");
+        assert_eq!(index.url().unwrap(), "http://127.0.0.1:5080");
+    }
+
+    #[cfg(not(target_arch="wasm32"))]
+    #[tokio::test]
+    async fn test_url_not_available() {
+        let (_controller, client) = stub_client(None).await;
+        let index = client.index("local");
+        assert!(matches!(index.describe_stats().await, Err(Error::URLNotAvailable)));
+    }
+
+    #[cfg(not(target_arch="wasm32"))]
+    #[tokio::test]
+    async fn test_describe() {
+        let (_mock, client) = create_client().await;
+        let index = create_index(&client).await;
+        let description = index.describe().await.unwrap();
+        assert_eq!(description.database.name, "pinenut");
+        assert_eq!(description.database.dimension, 4);
+    }
+
+    #[cfg(not(target_arch="wasm32"))]
+    #[tokio::test]
     async fn test_describe_stats() {
-        let client = create_client().await;
-        let mut index = create_index(&client).await;
+        let (_mock, client) = create_client().await;
+        let index = create_index(&client).await;
+        index.upsert(String::from("halfbaked"), vec![vector("A", vec![0.5; 4], Value::Null)]).await.unwrap();
         match index.describe_stats().await {
-            Ok(_) => assert!(true),
+            Ok(stats) => {
+                assert_eq!(stats.dimension, 4);
+                assert_eq!(stats.total_vector_count, 1);
+            },
             Err(err) => panic!("failed to get index stats: {:?}", err)
         }
     }
 
-    #[cfg_attr(not(target_arch="wasm32"), tokio::test)]
-    #[cfg_attr(target_arch="wasm32", wasm_bindgen_test)]
+    #[cfg(not(target_arch="wasm32"))]
+    #[tokio::test]
+    async fn test_describe_stats_filtered() {
+        let (_mock, client) = create_client().await;
+        let index = create_index(&client).await;
+        index.upsert(String::from("tenants"), vec![
+            vector("A", vec![0.5; 4], serde_json::json!({"tenant": "acme"})),
+            vector("B", vec![0.5; 4], serde_json::json!({"tenant": "acme"})),
+            vector("C", vec![0.5; 4], serde_json::json!({"tenant": "globex"}))
+        ]).await.unwrap();
+        assert_eq!(index.describe_stats().await.unwrap().total_vector_count, 3);
+
+        let acme = index.describe_stats_filtered(Filter::eq("tenant", "acme")).await.unwrap();
+        assert_eq!(acme.total_vector_count, 2);
+        assert_eq!(acme.namespaces["tenants"].vector_count, 2);
+        assert_eq!(index.stats().unwrap().total_vector_count, 3, "the unfiltered stats stay cached");
+    }
+
+    #[cfg(not(target_arch="wasm32"))]
+    #[tokio::test]
     async fn test_configure_index() {
-        let client = create_client().await;
+        let (_mock, client) = create_client().await;
         let index = create_index(&client).await;
-        match index.configure(1, "s1.x1".to_string()).await {
-            Ok(_) => assert!(true),
-            Err(error) => {
-                match error {
-                    Error::PineconeResponseError(code,typ,msg) => {
-                        if code == StatusCode::BAD_REQUEST {
-                            assert!(true);
-                            return;
-                        }
-                        panic!("Unable to configure index: {:?}", Error::PineconeResponseError(code, typ, msg))
-                    },
-                    _ => {
-                        panic!("Unable to configure index: {:?}", error)
-                    }
-                }
-            }
-        }
+        index.configure(2, "s1.x1".to_string()).await.unwrap();
+        let description = index.describe().await.unwrap();
+        assert_eq!(description.database.replicas, 2);
+        assert_eq!(description.database.pod_type.as_deref(), Some("s1.x1"));
     }
 
-    #[cfg_attr(not(target_arch="wasm32"), tokio::test)]
-    #[cfg_attr(target_arch="wasm32", wasm_bindgen_test)]
+    #[cfg(not(target_arch="wasm32"))]
+    #[tokio::test]
     async fn test_update_index(){
-        let client = create_client().await;
-        let mut index = create_index(&client).await;
+        let (_mock, client) = create_client().await;
+        let index = create_index(&client).await;
         let data = UpdateRequest{id: String::from("A"), ..Default::default()};
-        match index.update(data).await {
-            Ok(_) => assert!(true),
-            Err(error) => {
-                match error {
-                    Error::PineconeResponseError(code,typ,msg) => {
-                        if code == StatusCode::BAD_REQUEST {
-                            assert!(true);
-                            return;
-                        }
-                        panic!("Unable to configure index: {:?}", Error::PineconeResponseError(code, typ, msg))
-                    },
-                    _ => {
-                        panic!("Unable to configure index: {:?}", error)
-                    }
-                }
-            }
+        match index.update(data.clone()).await {
+            Err(Error::NotFound(err)) => {
+                assert_eq!((err.method, err.path.as_str()), (Method::POST, "/data/pinenut/vectors/update"));
+                assert_eq!(err.index.as_deref(), Some("pinenut"));
+            },
+            other => panic!("Expected a missing vector: {:?}", other)
         }
+
+        index.upsert(String::new(), vec![vector("A", vec![0.5; 4], serde_json::json!({"genre": "drama"}))]).await.unwrap();
+        let data = UpdateRequest{
+            values: Some(vec![1.0; 4]),
+            metadata: Some(serde_json::from_value(serde_json::json!({"year": 2020})).unwrap()),
+            ..data
+        };
+        index.update(data).await.unwrap();
+        let fetched = index.fetch(FetchRequest{ids: vec!["A".to_string()], namespace: None}).await.unwrap();
+        let vector = &fetched.vectors["A"];
+        assert_eq!(vector.values, vec![1.0; 4]);
+        assert_eq!(vector.metadata.as_ref().unwrap()["genre"], "drama");
+        assert_eq!(vector.metadata.as_ref().unwrap()["year"], 2020);
+    }
+
+    #[cfg(not(target_arch="wasm32"))]
+    #[tokio::test]
+    async fn test_delete_vectors(){
+        let (_mock, client) = create_client().await;
+        let index = create_index(&client).await;
+        index.upsert(String::from("halfbaked"), vec![
+            vector("A", vec![0.5; 4], serde_json::json!({"genre": "drama"})),
+            vector("B", vec![0.5; 4], Value::Null),
+            vector("C", vec![0.5; 4], serde_json::json!({"genre": "comedy"}))
+        ]).await.unwrap();
+        let data = DeleteRequest{ids: Some(vec!["B".to_string()]), namespace: Some(String::from("halfbaked")), ..Default::default()};
+        index.delete_vectors(data).await.unwrap();
+        let data = DeleteRequest{filter: Some(Filter::eq("genre", "comedy")), namespace: Some(String::from("halfbaked")), ..Default::default()};
+        index.delete_vectors(data).await.unwrap();
+        let fetched = index.fetch(FetchRequest{ids: vec!["A".to_string(), "B".to_string(), "C".to_string()], namespace: Some(String::from("halfbaked"))}).await.unwrap();
+        assert_eq!(fetched.vectors.keys().collect::<Vec<_>>(), vec!["A"]);
     }
 
     #[cfg_attr(not(target_arch="wasm32"), tokio::test)]
     #[cfg_attr(target_arch="wasm32", wasm_bindgen_test)]
+    async fn test_delete_vectors_invalid_request(){
+        let client = unconnected(ControlPlane::Legacy);
+        let index = client.index_by_host("pinenut", "http://127.0.0.1:1");
+        let data = DeleteRequest{ids: Some(vec!["B".to_string()]), delete_all: true, ..Default::default()};
+        match index.delete_vectors(data).await {
+            Err(Error::ArgumentError{name, ..}) => assert_eq!(name, "DeleteRequest"),
+            other => panic!("Expected an argument error: {:?}", other)
+        }
+    }
+
+    #[cfg(not(target_arch="wasm32"))]
+    #[tokio::test]
     async fn test_fetch_index(){
-        let client = create_client().await;
-        let mut index = create_index(&client).await;
-        let data = FetchRequest{ids: vec!["A".to_string()], namespace: Some(String::from("halfbaked"))};
+        let (_mock, client) = create_client().await;
+        let index = create_index(&client).await;
+        index.upsert(String::from("halfbaked"), vec![vector("A", vec![0.5; 4], Value::Null)]).await.unwrap();
+        let data = FetchRequest{ids: vec!["A".to_string(), "B".to_string()], namespace: Some(String::from("halfbaked"))};
         match index.fetch(data).await {
-            Ok(_) => assert!(true),
+            Ok(resp) => {
+                assert_eq!(resp.namespace, "halfbaked");
+                assert_eq!(resp.vectors.keys().collect::<Vec<_>>(), vec!["A"]);
+                assert_eq!(resp.vectors["A"].values, vec![0.5; 4]);
+            },
             Err(error) => panic!("Unable to fetch: {:?}", error)
         }
     }
 
-    #[cfg_attr(not(target_arch="wasm32"), tokio::test)]
-    #[cfg_attr(target_arch="wasm32", wasm_bindgen_test)]
+    #[cfg(not(target_arch="wasm32"))]
+    #[tokio::test]
+    async fn test_list_ids(){
+        let (_mock, client) = create_client().await;
+        let index = create_index(&client).await;
+        let ids = ["doc1#a", "doc1#b", "doc1#c", "doc2#a", "doc1#d"];
+        index.upsert(String::from("listed"), ids.iter().map(|id| vector(id, vec![0.5; 4], Value::Null)).collect()).await.unwrap();
+
+        let options = ListOptions{page_size: Some(2), ..Default::default()};
+        let listed: Vec<String> = index.list_ids("listed", Some(String::from("doc1#")), options).try_collect().await.unwrap();
+        assert_eq!(listed, vec!["doc1#a", "doc1#b", "doc1#c", "doc1#d"]);
+        let all: Vec<String> = index.list_ids("listed", None, ListOptions::default()).try_collect().await.unwrap();
+        assert_eq!(all.len(), 5);
+        let empty: Vec<String> = index.list_ids("missing", None, ListOptions::default()).try_collect().await.unwrap();
+        assert!(empty.is_empty());
+
+        let page = index.list_page(ListRequest{namespace: Some(String::from("listed")), limit: Some(3), ..Default::default()}).await.unwrap();
+        assert_eq!(page.vectors.len(), 3);
+        let options = ListOptions{page_size: Some(1), pagination_token: page.next_token().map(String::from)};
+        let resumed: Vec<String> = index.list_ids("listed", None, options).try_collect().await.unwrap();
+        assert_eq!(resumed, vec!["doc1#d", "doc2#a"]);
+
+        let mut invalid = Box::pin(index.list_ids("listed", None, ListOptions{page_size: Some(0), ..Default::default()}));
+        assert!(matches!(invalid.next().await, Some(Err(Error::ArgumentError{..}))));
+        assert!(invalid.next().await.is_none());
+    }
+
+    #[cfg(not(target_arch="wasm32"))]
+    #[tokio::test]
+    async fn test_export_import_jsonl(){
+        let (_mock, client) = create_client().await;
+        let index = create_index(&client).await;
+        let mut vectors: Vec<Vector> = (0..7).map(|i| vector(&format!("doc#{}", i), vec![i as f32; 4], serde_json::json!({"n": i}))).collect();
+        vectors[0].sparse_values = Some(SparseValues{indeces: vec![1, 3], values: vec![0.5, 0.25]});
+        index.upsert(String::from("source"), vectors).await.unwrap();
+
+        let mut file = Vec::new();
+        let mut reported = Vec::new();
+        let options = ExportOptions{page_size: Some(2), fetch_size: 3};
+        let exported = index.export_jsonl("source", &mut file, options, |p| reported.push(p)).await.unwrap();
+        assert_eq!(exported, 7);
+        assert_eq!(reported.iter().map(|p| p.vectors).collect::<Vec<_>>(), vec![3, 6, 7]);
+        assert!(reported.iter().all(|p| p.total == Some(7)));
+        let lines: Vec<Vector> = file.split(|b| *b == b'\n').filter(|l| !l.is_empty()).map(|l| serde_json::from_slice(l).unwrap()).collect();
+        assert_eq!(lines.len(), 7);
+        assert_eq!(lines[0].id, "doc#0");
+        assert_eq!(lines[0].sparse_values.as_ref().unwrap().indeces, vec![1, 3]);
+        assert_eq!(lines[6].metadata.as_ref().unwrap()["n"], 6);
+
+        let mut rounds = 0;
+        let options = BatchOptions{max_vectors: 2, concurrency: 2, ..Default::default()};
+        let imported = index.import_jsonl("copy", &file[..], options, |_| rounds += 1).await.unwrap();
+        assert!(imported.is_complete(), "failed chunks: {:?}", imported.failures);
+        assert_eq!((imported.response.upserted_count, rounds), (7, 2));
+        let fetched = index.fetch(FetchRequest{ids: vec![String::from("doc#0")], namespace: Some(String::from("copy"))}).await.unwrap();
+        assert_eq!(fetched.vectors["doc#0"].sparse_values.as_ref().unwrap().values, vec![0.5, 0.25]);
+
+        let invalid = b"{\"id\": \"a\", \"values\": [1, 1, 1, 1]}\n\nnot json\n";
+        match index.import_jsonl("invalid", &invalid[..], BatchOptions::default(), |_| {}).await {
+            Err(Error::ArgumentError{name, ..}) => assert_eq!(name, "line 3"),
+            other => panic!("Expected an invalid line: {:?}", other)
+        }
+        assert!(matches!(index.export_jsonl("source", Vec::new(), ExportOptions{fetch_size: 0, ..Default::default()}, |_| {}).await, Err(Error::ArgumentError{..})));
+    }
+
+    #[cfg(not(target_arch="wasm32"))]
+    #[tokio::test]
     async fn test_query_index(){
-        let client = create_client().await;
-        let mut index = create_index(&client).await;
-        let data = QueryRequest{id: Some(String::from("A")), top_k: 1, ..Default::default()};
+        let (_mock, client) = create_client().await;
+        let index = create_index(&client).await;
+        index.upsert(String::new(), vec![
+            vector("A", vec![1.0, 0.0, 0.0, 0.0], serde_json::json!({"genre": "drama"})),
+            vector("B", vec![0.9, 0.1, 0.0, 0.0], serde_json::json!({"genre": "comedy"})),
+            vector("C", vec![0.0, 0.0, 0.0, 1.0], serde_json::json!({"genre": "drama"}))
+        ]).await.unwrap();
+        let data = QueryRequest{id: Some(String::from("A")), top_k: 2, ..Default::default()};
         match index.query(data).await {
-            Ok(_) => assert!(true),
-            Err(error) => panic!("Unable to fetch: {:?}", error)
+            Ok(resp) => assert_eq!(resp.matches.iter().map(|m| m.id.as_str()).collect::<Vec<_>>(), vec!["A", "B"]),
+            Err(error) => panic!("Unable to query: {:?}", error)
         }
+        let data = QueryRequest{
+            vector: Some(vec![1.0, 0.0, 0.0, 0.0]),
+            top_k: 2,
+            filter: Some(Filter::eq("genre", "drama")),
+            include_metadata: true,
+            ..Default::default()
+        };
+        let resp = index.query(data).await.unwrap();
+        assert_eq!(resp.matches.iter().map(|m| m.id.as_str()).collect::<Vec<_>>(), vec!["A", "C"]);
+        assert!(resp.matches.iter().all(|m| m.metadata.is_some() && m.values.is_none()));
     }
 
-}
-
+    fn assert_shareable<T: Clone + Send + Sync + 'static>() {}
+
+    #[cfg(not(target_arch="wasm32"))]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_queries(){
+        assert_shareable::<Index>();
+        let (_mock, client) = create_client().await;
+        let index = Arc::new(create_index(&client).await);
+        index.upsert(String::new(), (0..16).map(|i| vector(&format!("V{}", i), vec![i as f32, 1.0, 0.0, 0.0], Value::Null)).collect()).await.unwrap();
+
+        let tasks: Vec<_> = (0..16).map(|i| {
+            let index = Arc::clone(&index);
+            tokio::spawn(async move {
+                let data = QueryRequest{id: Some(format!("V{}", i)), top_k: 1, ..Default::default()};
+                let matched = index.query(data).await.unwrap().matches.remove(0).id;
+                index.describe_stats().await.unwrap();
+                matched
+            })
+        }).collect();
+        for (i, task) in tasks.into_iter().enumerate() {
+            assert_eq!(task.await.unwrap(), format!("V{}", i));
+        }
 
+        let clone = (*index).clone();
+        assert_eq!(clone.stats().unwrap().total_vector_count, 16, "clones share the cached stats");
+        clone.describe().await.unwrap();
+        assert!(index.description().is_some());
+    }
 
+}
@@ -0,0 +1,357 @@
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock}
+};
+
+use futures::{Stream, lock::Mutex};
+use reqwest::{Method, StatusCode};
+use serde_json::Value;
+
+use crate::{Error, Result, error::ResponseError};
+use super::{
+    index::paginate,
+    memory::MemoryIndex,
+    models::{
+        DeleteRequest,
+        FetchRequest,
+        FetchResponse,
+        Filter,
+        IndexStats,
+        ListOptions,
+        ListRequest,
+        ListResponse,
+        Metric,
+        QueryRequest,
+        QueryResponse,
+        UpdateRequest,
+        UpsertResponse,
+        Vector
+    }
+};
+
+/// An index kept entirely in memory, offering the data plane operations of [`Index`] without a
+/// Pinecone account.
+///
+/// Queries are exact, every vector of the namespace is scored with the [`Metric`] of the index the
+/// way Pinecone scores them, and metadata filters are applied before scoring. A [`LocalIndex`]
+/// created with [`LocalIndex::open`] writes every change through to a file and is loaded back from
+/// it when opened again. Clones share the same vectors.
+///
+/// ```
+/// use pinenut::{LocalIndex, models::{Metric, QueryRequest, Vector}};
+///
+/// # async fn run() -> pinenut::Result<()> {
+/// let index = LocalIndex::new(2, Metric::COSINE);
+/// let vector = Vector{id: "A".to_string(), values: vec![0.5, 0.5], ..Default::default()};
+/// index.upsert(String::new(), vec![vector]).await?;
+///
+/// let query = QueryRequest{vector: Some(vec![1.0, 0.0]), top_k: 1, ..Default::default()};
+/// let response = index.query(query).await?;
+/// assert_eq!(response.matches[0].id, "A");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Index`]: crate::Index
+#[derive(Clone)]
+pub struct LocalIndex {
+    inner: Arc<Local>
+}
+
+struct Local {
+    data: RwLock<MemoryIndex>,
+    path: Option<PathBuf>,
+    /// Held for the whole of a write to a file backed index, so that changes are persisted in
+    /// the order they're applied.
+    writing: Mutex<()>
+}
+
+/// Writes `data` to `path`, replacing the previous file only once the new one is complete.
+fn persist(path: &Path, data: &MemoryIndex) -> Result<()> {
+    let mut tmp = path.to_path_buf().into_os_string();
+    tmp.push(".tmp");
+    let mut writer = BufWriter::new(File::create(&tmp).map_err(Error::IoError)?);
+    serde_json::to_writer(&mut writer, data).map_err(Error::JsonError)?;
+    writer.flush().map_err(Error::IoError)?;
+    fs::rename(&tmp, path).map_err(Error::IoError)
+}
+
+/// Persists `data` on its own thread so that writing the file doesn't stall the async runtime,
+/// handing `data` back once it's written.
+#[cfg(not(target_arch = "wasm32"))]
+async fn persist_off_runtime(path: PathBuf, data: MemoryIndex) -> Result<MemoryIndex> {
+    let (sender, receiver) = futures::channel::oneshot::channel();
+    std::thread::spawn(move || {
+        let result = persist(&path, &data).map(|()| data);
+        let _ = sender.send(result);
+    });
+    receiver.await.map_err(|_| Error::IoError(std::io::Error::other("the thread writing the index stopped")))?
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn persist_off_runtime(path: PathBuf, data: MemoryIndex) -> Result<MemoryIndex> {
+    persist(&path, &data).map(|()| data)
+}
+
+impl LocalIndex {
+
+    /// Creates an empty, in memory only, index for vectors of the given dimension.
+    pub fn new(dimension: usize, metric: Metric) -> LocalIndex {
+        LocalIndex::from_data(MemoryIndex::new(dimension, metric), None)
+    }
+
+    /// Opens the index stored at `path`, creating the file if it doesn't exist yet. Every change
+    /// to the index is written to the file.
+    ///
+    /// # Error
+    ///
+    /// Returns an [`Error::IoError`] or [`Error::JsonError`] if the file can't be read, written or
+    /// decoded, and an [`Error::ArgumentError`] if the stored index has a different dimension or
+    /// metric.
+    pub fn open(path: impl AsRef<Path>, dimension: usize, metric: Metric) -> Result<LocalIndex> {
+        let path = path.as_ref().to_path_buf();
+        let data = match File::open(&path) {
+            Ok(file) => {
+                let data: MemoryIndex = serde_json::from_reader(BufReader::new(file)).map_err(Error::JsonError)?;
+                if data.dimension != dimension || data.metric != metric {
+                    return Err(Error::ArgumentError {
+                        name: "path".to_string(),
+                        found: format!("an index of dimension {} with metric {}", data.dimension, data.metric),
+                        expected: format!("an index of dimension {} with metric {}", dimension, metric)
+                    });
+                }
+                data
+            },
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                let data = MemoryIndex::new(dimension, metric);
+                persist(&path, &data)?;
+                data
+            },
+            Err(err) => return Err(Error::IoError(err))
+        };
+        Ok(LocalIndex::from_data(data, Some(path)))
+    }
+
+    fn from_data(data: MemoryIndex, path: Option<PathBuf>) -> LocalIndex {
+        LocalIndex {
+            inner: Arc::new(Local {
+                data: RwLock::new(data),
+                path,
+                writing: Mutex::new(())
+            })
+        }
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, MemoryIndex> {
+        self.inner.data.read().unwrap()
+    }
+
+    /// Applies a change to the vectors and persists it. A file backed index applies the change
+    /// to a copy which only replaces the vectors once it's written, so that a failed write leaves
+    /// the index as it was.
+    async fn write<T>(&self, change: impl FnOnce(&mut MemoryIndex) -> Result<T>) -> Result<T> {
+        let path = match self.inner.path {
+            Some(ref path) => path.clone(),
+            None => return change(&mut self.inner.data.write().unwrap())
+        };
+        let _writing = self.inner.writing.lock().await;
+        let mut data = self.read().clone();
+        let value = change(&mut data)?;
+        let data = persist_off_runtime(path, data).await?;
+        *self.inner.data.write().unwrap() = data;
+        Ok(value)
+    }
+
+    /// The dimension of the vectors within the index.
+    pub fn dimension(&self) -> usize {
+        self.read().dimension
+    }
+
+    /// The metric queries are scored with.
+    pub fn metric(&self) -> Metric {
+        self.read().metric
+    }
+
+    /// Inserts or replaces `vectors` within `namespace`, see [`Index::upsert`].
+    ///
+    /// # Error
+    ///
+    /// Returns an [`Error::VectorDimensionError`], without upserting any vector, if a vector has
+    /// the wrong dimension.
+    ///
+    /// [`Index::upsert`]: crate::Index::upsert
+    pub async fn upsert(&self, namespace: String, vectors: Vec<Vector>) -> Result<UpsertResponse> {
+        self.write(|data| data.upsert(&namespace, vectors)).await
+    }
+
+    /// Returns the [`QueryRequest::top_k`] closest vectors of a namespace, see [`Index::query`].
+    ///
+    /// [`Index::query`]: crate::Index::query
+    pub async fn query(&self, request: QueryRequest) -> Result<QueryResponse> {
+        self.read().query(&request)
+    }
+
+    /// Looks up vectors by id, ids without a vector are left out of the response.
+    pub async fn fetch(&self, request: FetchRequest) -> Result<FetchResponse> {
+        let namespace = request.namespace.unwrap_or_default();
+        Ok(self.read().fetch(&request.ids, &namespace))
+    }
+
+    /// Updates the values of a vector and merges in its new metadata, see [`Index::update`].
+    ///
+    /// # Error
+    ///
+    /// Returns an [`Error::NotFound`] if there is no vector with the request id, like a hosted
+    /// index does.
+    ///
+    /// [`Index::update`]: crate::Index::update
+    pub async fn update(&self, request: UpdateRequest) -> Result<Value> {
+        let id = request.id.clone();
+        if !self.write(|data| data.update(request)).await? {
+            let message = format!("vector {} not found", id);
+            return Err(ResponseError::new(StatusCode::NOT_FOUND, Method::POST, "/vectors/update".to_string(), None, message, None).into_error());
+        }
+        Ok(serde_json::json!({}))
+    }
+
+    /// Deletes vectors by id, by metadata filter or every vector of a namespace, see
+    /// [`Index::delete_vectors`].
+    ///
+    /// [`Index::delete_vectors`]: crate::Index::delete_vectors
+    pub async fn delete_vectors(&self, request: DeleteRequest) -> Result<Value> {
+        self.write(|data| data.delete(&request)).await?;
+        Ok(serde_json::json!({}))
+    }
+
+    /// Lists a single page of the vector ids within a namespace, see [`Index::list_page`].
+    ///
+    /// [`Index::list_page`]: crate::Index::list_page
+    pub async fn list_page(&self, request: ListRequest) -> Result<ListResponse> {
+        request.validate()?;
+        Ok(self.read().list(
+            request.namespace.as_deref().unwrap_or_default(),
+            request.prefix.as_deref().unwrap_or_default(),
+            request.limit.unwrap_or(100),
+            request.pagination_token.as_deref()
+        ))
+    }
+
+    /// Streams the ids of every vector within `namespace` starting with `prefix`, see
+    /// [`Index::list_ids`].
+    ///
+    /// [`Index::list_ids`]: crate::Index::list_ids
+    pub fn list_ids(&self, namespace: impl Into<String>, prefix: Option<String>, options: ListOptions) -> impl Stream<Item = Result<String>> + '_ {
+        paginate(ListRequest::first(namespace.into(), prefix, options), move |request| self.list_page(request))
+    }
+
+    /// Returns the number of vectors within each namespace.
+    pub async fn describe_stats(&self) -> Result<IndexStats> {
+        Ok(self.read().stats())
+    }
+
+    /// Returns the number of vectors matching `filter` within each namespace, see
+    /// [`Index::describe_stats_filtered`].
+    ///
+    /// [`Index::describe_stats_filtered`]: crate::Index::describe_stats_filtered
+    pub async fn describe_stats_filtered(&self, filter: Filter) -> Result<IndexStats> {
+        Ok(self.read().filtered_stats(Some(&filter)))
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod local_test {
+
+    use super::*;
+    use futures::TryStreamExt;
+    use crate::rest::memory::vector;
+
+    fn temp_path() -> PathBuf {
+        std::env::temp_dir().join(format!("pinenut-local-{}.json", fastrand::u64(..)))
+    }
+
+    #[tokio::test]
+    async fn test_operations() {
+        let index = LocalIndex::new(3, Metric::DOTPRODUCT);
+        index.upsert("ns".to_string(), vec![
+            vector("a", vec![1.0, 0.0, 0.0], serde_json::json!({"kind": "x"})),
+            vector("b", vec![2.0, 2.0, 0.0], serde_json::json!({"kind": "y"}))
+        ]).await.unwrap();
+        index.upsert(String::new(), vec![vector("c", vec![0.0, 0.0, 1.0], Value::Null)]).await.unwrap();
+
+        let query = QueryRequest{namespace: Some("ns".to_string()), vector: Some(vec![1.0, 1.0, 0.0]), top_k: 2, ..Default::default()};
+        let response = index.query(query.clone()).await.unwrap();
+        assert_eq!(response.matches.iter().map(|m| (m.id.as_str(), m.score)).collect::<Vec<_>>(), vec![("b", Some(4.0)), ("a", Some(1.0))]);
+        let filtered = QueryRequest{filter: Some(Filter::eq("kind", "x")), ..query};
+        assert_eq!(index.query(filtered).await.unwrap().matches.len(), 1);
+
+        index.update(UpdateRequest{id: "a".to_string(), values: Some(vec![5.0, 5.0, 5.0]), namespace: Some("ns".to_string()), ..Default::default()}).await.unwrap();
+        assert!(matches!(index.update(UpdateRequest{id: "z".to_string(), ..Default::default()}).await, Err(Error::NotFound(_))));
+        let fetched = index.fetch(FetchRequest{ids: vec!["a".to_string()], namespace: Some("ns".to_string())}).await.unwrap();
+        assert_eq!(fetched.vectors["a"].values, vec![5.0, 5.0, 5.0]);
+
+        let stats = index.describe_stats().await.unwrap();
+        assert_eq!((stats.total_vector_count, stats.namespaces["ns"].vector_count, stats.namespaces[""].vector_count), (3, 2, 1));
+        assert_eq!(index.describe_stats_filtered(Filter::eq("kind", "y")).await.unwrap().total_vector_count, 1);
+        index.delete_vectors(DeleteRequest{delete_all: true, namespace: Some("ns".to_string()), ..Default::default()}).await.unwrap();
+        assert_eq!(index.clone().describe_stats().await.unwrap().total_vector_count, 1);
+        assert!(matches!(index.upsert(String::new(), vec![vector("d", vec![1.0], Value::Null)]).await, Err(Error::VectorDimensionError{..})));
+
+        index.upsert("ns".to_string(), vec![vector("x1", vec![1.0, 0.0, 0.0], Value::Null), vector("x2", vec![0.0, 1.0, 0.0], Value::Null), vector("y", vec![0.0, 0.0, 1.0], Value::Null)]).await.unwrap();
+        let options = ListOptions{page_size: Some(1), ..Default::default()};
+        let listed: Vec<String> = index.list_ids("ns", Some("x".to_string()), options).try_collect().await.unwrap();
+        assert_eq!(listed, vec!["x1", "x2"]);
+        assert!(matches!(index.list_page(ListRequest{limit: Some(0), ..Default::default()}).await, Err(Error::ArgumentError{..})));
+    }
+
+    #[tokio::test]
+    async fn test_persistence() {
+        let path = temp_path();
+        let index = LocalIndex::open(&path, 2, Metric::EUCLIDEAN).unwrap();
+        assert!(path.exists());
+        index.upsert("ns".to_string(), vec![vector("a", vec![1.0, 2.0], serde_json::json!({"kind": "x"}))]).await.unwrap();
+        index.upsert("ns".to_string(), vec![vector("b", vec![3.0, 4.0], Value::Null)]).await.unwrap();
+        index.delete_vectors(DeleteRequest{ids: Some(vec!["b".to_string()]), namespace: Some("ns".to_string()), ..Default::default()}).await.unwrap();
+        drop(index);
+
+        let reopened = LocalIndex::open(&path, 2, Metric::EUCLIDEAN).unwrap();
+        let fetched = reopened.fetch(FetchRequest{ids: vec!["a".to_string(), "b".to_string()], namespace: Some("ns".to_string())}).await.unwrap();
+        assert_eq!(fetched.vectors.len(), 1);
+        assert_eq!(fetched.vectors["a"].metadata.as_ref().unwrap()["kind"], "x");
+
+        match LocalIndex::open(&path, 3, Metric::EUCLIDEAN) {
+            Err(Error::ArgumentError{name, ..}) => assert_eq!(name, "path"),
+            other => panic!("Expected a mismatched index: {:?}", other.map(|i| i.dimension()))
+        }
+        fs::write(&path, "not json").unwrap();
+        assert!(matches!(LocalIndex::open(&path, 2, Metric::EUCLIDEAN), Err(Error::JsonError(_))));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_failed_persistence() {
+        let path = temp_path();
+        let index = LocalIndex::open(&path, 2, Metric::EUCLIDEAN).unwrap();
+        index.upsert(String::new(), vec![vector("a", vec![1.0, 2.0], Value::Null)]).await.unwrap();
+
+        // a directory where the temporary file goes makes every write fail, even as root.
+        let mut tmp = path.clone().into_os_string();
+        tmp.push(".tmp");
+        fs::create_dir(&tmp).unwrap();
+        let upsert = index.upsert(String::new(), vec![vector("b", vec![3.0, 4.0], Value::Null)]).await;
+        assert!(matches!(upsert, Err(Error::IoError(_))));
+        let delete = index.delete_vectors(DeleteRequest{delete_all: true, ..Default::default()}).await;
+        assert!(matches!(delete, Err(Error::IoError(_))));
+
+        let fetched = index.fetch(FetchRequest{ids: vec!["a".to_string(), "b".to_string()], namespace: None}).await.unwrap();
+        assert_eq!(fetched.vectors.keys().collect::<Vec<_>>(), vec!["a"]);
+
+        fs::remove_dir(&tmp).unwrap();
+        index.upsert(String::new(), vec![vector("c", vec![5.0, 6.0], Value::Null)]).await.unwrap();
+        let reopened = LocalIndex::open(&path, 2, Metric::EUCLIDEAN).unwrap();
+        let stored = reopened.fetch(FetchRequest{ids: vec!["a".to_string(), "b".to_string(), "c".to_string()], namespace: None}).await.unwrap();
+        assert_eq!(stored.vectors.keys().collect::<Vec<_>>(), vec!["a", "c"]);
+        fs::remove_file(&path).unwrap();
+    }
+}
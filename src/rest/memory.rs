@@ -0,0 +1,406 @@
+//! An in-memory, brute force, vector store implementing the semantics of Pinecone's data plane.
+
+use std::{cmp::Ordering, collections::{BTreeMap, HashMap}};
+
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+
+use crate::{Error, Result};
+use super::models::{
+    DeleteRequest,
+    FetchResponse,
+    Filter,
+    IndexStats,
+    ListItem,
+    ListResponse,
+    MappedValue,
+    Match,
+    Metric,
+    Namespace,
+    Pagination,
+    QueryRequest,
+    QueryResponse,
+    UpdateRequest,
+    UpsertResponse,
+    Vector
+};
+
+/// The vectors of an index, grouped by namespace.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct MemoryIndex {
+    pub(crate) dimension: usize,
+    pub(crate) metric: Metric,
+    namespaces: BTreeMap<String, BTreeMap<String, Vector>>
+}
+
+impl MemoryIndex {
+
+    pub(crate) fn new(dimension: usize, metric: Metric) -> MemoryIndex {
+        MemoryIndex {
+            dimension,
+            metric,
+            namespaces: BTreeMap::new()
+        }
+    }
+
+    fn check_dimension(&self, id: &str, values: &[f32]) -> Result<()> {
+        if values.len() != self.dimension {
+            return Err(Error::VectorDimensionError {
+                found: values.len() as u32,
+                expected: self.dimension as u32,
+                id: id.to_string()
+            });
+        }
+        Ok(())
+    }
+
+    /// Inserts or replaces `vectors`, none are written if any has the wrong dimension.
+    pub(crate) fn upsert(&mut self, namespace: &str, vectors: Vec<Vector>) -> Result<UpsertResponse> {
+        for vector in &vectors {
+            self.check_dimension(&vector.id, &vector.values)?;
+        }
+        let upserted_count = vectors.len();
+        let namespace = self.namespaces.entry(namespace.to_string()).or_default();
+        for vector in vectors {
+            namespace.insert(vector.id.clone(), vector);
+        }
+        Ok(UpsertResponse{upserted_count})
+    }
+
+    /// Returns the `top_k` vectors closest to the query vector, or to the vector with the query
+    /// id, that match the query filter.
+    pub(crate) fn query(&self, request: &QueryRequest) -> Result<QueryResponse> {
+        let namespace = request.namespace.clone().unwrap_or_default();
+        if request.top_k == 0 {
+            return Err(Error::ArgumentError {
+                name: "top_k".to_string(),
+                found: "0".to_string(),
+                expected: "at least 1".to_string()
+            });
+        }
+        let vectors = self.namespaces.get(&namespace);
+        let query = match (&request.vector, &request.id) {
+            (Some(values), None) => {
+                self.check_dimension("query", values)?;
+                values
+            },
+            (None, Some(id)) => match vectors.and_then(|v| v.get(id)) {
+                Some(vector) => &vector.values,
+                None => return Ok(QueryResponse{matches: Vec::new(), namespace})
+            },
+            _ => return Err(Error::ArgumentError {
+                name: "QueryRequest".to_string(),
+                found: format!("vector: {}, id: {}", request.vector.is_some(), request.id.is_some()),
+                expected: "exactly one of vector or id".to_string()
+            })
+        };
+        let filter = request.filter.as_ref().map(Filter::to_value);
+        let mut scored: Vec<(f32, &Vector)> = vectors.into_iter()
+            .flat_map(BTreeMap::values)
+            .filter(|v| filter.as_ref().is_none_or(|f| matches_filter(f, v.metadata.as_ref())))
+            .map(|v| (score(self.metric, query, &v.values), v))
+            .collect();
+        // euclidean scores are distances, lower is closer.
+        scored.sort_by(|a, b| match self.metric {
+            Metric::EUCLIDEAN => a.0.partial_cmp(&b.0),
+            _ => b.0.partial_cmp(&a.0)
+        }.unwrap_or(Ordering::Equal));
+        let matches = scored.into_iter()
+            .take(request.top_k)
+            .map(|(score, v)| Match {
+                id: v.id.clone(),
+                score: Some(score),
+                values: request.include_values.then(|| v.values.clone()),
+                sparse_values: if request.include_values { v.sparse_values.clone() } else { None },
+                metadata: if request.include_metadata { v.metadata.clone() } else { None }
+            })
+            .collect();
+        Ok(QueryResponse{matches, namespace})
+    }
+
+    /// Returns the vectors with the given ids, missing ids are left out.
+    pub(crate) fn fetch(&self, ids: &[String], namespace: &str) -> FetchResponse {
+        let vectors = self.namespaces.get(namespace)
+            .map(|vectors| ids.iter().filter_map(|id| vectors.get(id)).map(|v| (v.id.clone(), v.clone())).collect())
+            .unwrap_or_default();
+        FetchResponse {
+            vectors,
+            namespace: namespace.to_string()
+        }
+    }
+
+    /// Returns up to `limit` ids of a namespace starting with `prefix`, in order, continuing after
+    /// the id given as the token. The token of the next page is the last id of the page.
+    pub(crate) fn list(&self, namespace: &str, prefix: &str, limit: usize, token: Option<&str>) -> ListResponse {
+        let mut ids = self.namespaces.get(namespace).into_iter()
+            .flat_map(|vectors| vectors.keys())
+            .filter(|id| id.starts_with(prefix) && token.is_none_or(|token| id.as_str() > token));
+        let page: Vec<ListItem> = ids.by_ref().take(limit).map(|id| ListItem{id: id.clone()}).collect();
+        let next = match (ids.next(), page.last()) {
+            (Some(_), Some(last)) => Some(last.id.clone()),
+            _ => None
+        };
+        ListResponse {
+            vectors: page,
+            pagination: next.map(|next| Pagination{next: Some(next)}),
+            namespace: namespace.to_string()
+        }
+    }
+
+    /// Updates the values of a vector and merges in its new metadata, returning false if there is
+    /// no vector with the request id.
+    pub(crate) fn update(&mut self, request: UpdateRequest) -> Result<bool> {
+        if let Some(ref values) = request.values {
+            self.check_dimension(&request.id, values)?;
+        }
+        let namespace = request.namespace.unwrap_or_default();
+        let vector = match self.namespaces.get_mut(&namespace).and_then(|v| v.get_mut(&request.id)) {
+            Some(vector) => vector,
+            None => return Ok(false)
+        };
+        if let Some(values) = request.values {
+            vector.values = values;
+        }
+        if let Some(sparse_values) = request.sparse_values {
+            vector.sparse_values = Some(sparse_values);
+        }
+        if let Some(metadata) = request.metadata {
+            vector.metadata.get_or_insert_with(MappedValue::new).extend(metadata);
+        }
+        Ok(true)
+    }
+
+    /// Deletes the vectors targeted by `request`, see [`DeleteRequest`].
+    pub(crate) fn delete(&mut self, request: &DeleteRequest) -> Result<()> {
+        request.validate()?;
+        let namespace = request.namespace.clone().unwrap_or_default();
+        if request.delete_all {
+            self.namespaces.remove(&namespace);
+            return Ok(());
+        }
+        let vectors = match self.namespaces.get_mut(&namespace) {
+            Some(vectors) => vectors,
+            None => return Ok(())
+        };
+        if let Some(ref ids) = request.ids {
+            for id in ids {
+                vectors.remove(id);
+            }
+        }
+        if let Some(ref filter) = request.filter {
+            let filter = filter.to_value();
+            vectors.retain(|_, v| !matches_filter(&filter, v.metadata.as_ref()));
+        }
+        if vectors.is_empty() {
+            self.namespaces.remove(&namespace);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn stats(&self) -> IndexStats {
+        self.filtered_stats(None)
+    }
+
+    /// The stats of the vectors matching `filter`, namespaces without a match are left out.
+    pub(crate) fn filtered_stats(&self, filter: Option<&Filter>) -> IndexStats {
+        let filter = filter.map(Filter::to_value);
+        let namespaces: HashMap<String, Namespace> = self.namespaces.iter()
+            .map(|(name, vectors)| {
+                let vector_count = match filter {
+                    Some(ref filter) => vectors.values().filter(|v| matches_filter(filter, v.metadata.as_ref())).count(),
+                    None => vectors.len()
+                };
+                (name.clone(), Namespace{vector_count})
+            })
+            .filter(|(_, namespace)| namespace.vector_count > 0)
+            .collect();
+        IndexStats {
+            total_vector_count: namespaces.values().map(|n| n.vector_count as u32).sum(),
+            namespaces,
+            dimension: self.dimension,
+            index_fullness: 0
+        }
+    }
+}
+
+/// Scores `b` against `a` the way Pinecone does for `metric`, the euclidean score being the
+/// squared distance.
+pub(crate) fn score(metric: Metric, a: &[f32], b: &[f32]) -> f32 {
+    let dot = || a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>();
+    match metric {
+        Metric::DOTPRODUCT => dot(),
+        Metric::COSINE => {
+            let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+            let norms = norm(a) * norm(b);
+            if norms == 0.0 { 0.0 } else { dot() / norms }
+        },
+        Metric::EUCLIDEAN => a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum()
+    }
+}
+
+/// Whether `metadata` matches a Pinecone metadata filter, such as the value of
+/// [`Filter::to_value`].
+pub(crate) fn matches_filter(filter: &Value, metadata: Option<&MappedValue>) -> bool {
+    let conditions = match filter {
+        Value::Object(conditions) => conditions,
+        _ => return false
+    };
+    conditions.iter().all(|(key, condition)| match key.as_str() {
+        "$and" => condition.as_array().is_some_and(|f| f.iter().all(|f| matches_filter(f, metadata))),
+        "$or" => condition.as_array().is_some_and(|f| f.iter().any(|f| matches_filter(f, metadata))),
+        field => {
+            let value = metadata.and_then(|m| m.get(field));
+            match condition {
+                Value::Object(operators) => operators.iter().all(|(op, operand)| matches_operator(value, op, operand)),
+                operand => matches_operator(value, "$eq", operand)
+            }
+        }
+    })
+}
+
+fn matches_operator(value: Option<&Value>, operator: &str, operand: &Value) -> bool {
+    let compare = |accept: fn(Ordering) -> bool| {
+        match (value.and_then(Value::as_f64), operand.as_f64()) {
+            (Some(value), Some(operand)) => value.partial_cmp(&operand).is_some_and(accept),
+            _ => false
+        }
+    };
+    let is_in = || operand.as_array().is_some_and(|o| o.iter().any(|o| contains(value, o)));
+    match operator {
+        "$eq" => contains(value, operand),
+        "$ne" => !contains(value, operand),
+        "$gt" => compare(Ordering::is_gt),
+        "$gte" => compare(Ordering::is_ge),
+        "$lt" => compare(Ordering::is_lt),
+        "$lte" => compare(Ordering::is_le),
+        "$in" => is_in(),
+        "$nin" => !is_in(),
+        "$exists" => value.is_some() == operand.as_bool().unwrap_or(true),
+        _ => false
+    }
+}
+
+/// Whether a metadata value equals `operand`, or contains it when the value is a list.
+fn contains(value: Option<&Value>, operand: &Value) -> bool {
+    let equals = |value: &Value| match (value.as_f64(), operand.as_f64()) {
+        (Some(value), Some(operand)) => value == operand,
+        _ => value == operand
+    };
+    match value {
+        Some(Value::Array(values)) => values.iter().any(equals),
+        Some(value) => equals(value),
+        None => false
+    }
+}
+
+/// Creates a vector for the tests of every index, `metadata` being a json object or null.
+#[cfg(test)]
+pub(crate) fn vector(id: &str, values: Vec<f32>, metadata: Value) -> Vector {
+    Vector {
+        id: id.to_string(),
+        values,
+        sparse_values: None,
+        metadata: serde_json::from_value(metadata).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod memory_test {
+
+    use super::*;
+    use serde_json::json;
+
+    fn index(metric: Metric) -> MemoryIndex {
+        let mut index = MemoryIndex::new(2, metric);
+        index.upsert("", vec![
+            vector("a", vec![1.0, 0.0], json!({"genre": "drama", "year": 2001})),
+            vector("b", vec![0.0, 1.0], json!({"genre": "comedy", "year": 2010, "tags": ["funny", "short"]})),
+            vector("c", vec![2.0, 2.0], json!({"genre": "drama", "year": 2020}))
+        ]).unwrap();
+        index
+    }
+
+    fn ids(response: QueryResponse) -> Vec<String> {
+        response.matches.into_iter().map(|m| m.id).collect()
+    }
+
+    fn query(values: Vec<f32>, top_k: usize, filter: Option<Filter>) -> QueryRequest {
+        QueryRequest{vector: Some(values), top_k, filter, ..Default::default()}
+    }
+
+    #[test]
+    fn test_score() {
+        assert_eq!(score(Metric::DOTPRODUCT, &[1.0, 2.0], &[3.0, 4.0]), 11.0);
+        assert_eq!(score(Metric::EUCLIDEAN, &[1.0, 2.0], &[3.0, 4.0]), 8.0);
+        assert!((score(Metric::COSINE, &[1.0, 1.0], &[2.0, 2.0]) - 1.0).abs() < 1e-6);
+        assert_eq!(score(Metric::COSINE, &[0.0, 0.0], &[2.0, 2.0]), 0.0);
+    }
+
+    #[test]
+    fn test_query_order() {
+        assert_eq!(ids(index(Metric::COSINE).query(&query(vec![1.0, 0.1], 3, None)).unwrap()), vec!["a", "c", "b"]);
+        assert_eq!(ids(index(Metric::DOTPRODUCT).query(&query(vec![1.0, 0.1], 2, None)).unwrap()), vec!["c", "a"]);
+        assert_eq!(ids(index(Metric::EUCLIDEAN).query(&query(vec![1.5, 1.5], 3, None)).unwrap()), vec!["c", "a", "b"]);
+
+        let by_id = QueryRequest{id: Some("b".to_string()), top_k: 1, include_values: true, ..Default::default()};
+        let response = index(Metric::COSINE).query(&by_id).unwrap();
+        assert_eq!(response.matches[0].id, "b");
+        assert_eq!(response.matches[0].values, Some(vec![0.0, 1.0]));
+        assert!(response.matches[0].metadata.is_none());
+    }
+
+    #[test]
+    fn test_query_filter() {
+        let index = index(Metric::COSINE);
+        let filtered = |filter: Filter| ids(index.query(&query(vec![1.0, 0.0], 3, Some(filter))).unwrap());
+        assert_eq!(filtered(Filter::eq("genre", "drama")), vec!["a", "c"]);
+        assert_eq!(filtered(Filter::eq("genre", "drama").and(Filter::gt("year", 2010))), vec!["c"]);
+        assert_eq!(filtered(Filter::lte("year", 2010).or(Filter::ne("genre", "drama"))), vec!["a", "b"]);
+        assert_eq!(filtered(Filter::is_in("tags", ["short"])), vec!["b"]);
+        assert_eq!(filtered(Filter::not_in("genre", ["comedy"])), vec!["a", "c"]);
+        assert_eq!(filtered(Filter::exists("tags", false)), vec!["a", "c"]);
+        assert_eq!(filtered(Filter::Raw(serde_json::from_value(json!({"year": 2020})).unwrap())), vec!["c"]);
+    }
+
+    #[test]
+    fn test_invalid_requests() {
+        let mut index = index(Metric::COSINE);
+        assert!(matches!(index.upsert("", vec![vector("d", vec![1.0], Value::Null)]), Err(Error::VectorDimensionError{..})));
+        assert!(matches!(index.query(&query(vec![1.0, 0.0], 0, None)), Err(Error::ArgumentError{..})));
+        assert!(matches!(index.query(&QueryRequest{top_k: 1, ..Default::default()}), Err(Error::ArgumentError{..})));
+        assert_eq!(index.stats().total_vector_count, 3);
+    }
+
+    #[test]
+    fn test_update_and_delete() {
+        let mut index = index(Metric::COSINE);
+        let update = UpdateRequest {
+            id: "a".to_string(),
+            values: Some(vec![0.5, 0.5]),
+            metadata: serde_json::from_value(json!({"year": 2002})).unwrap(),
+            ..Default::default()
+        };
+        assert!(index.update(update).unwrap());
+        assert!(!index.update(UpdateRequest{id: "z".to_string(), ..Default::default()}).unwrap());
+        let fetched = index.fetch(&["a".to_string(), "z".to_string()], "");
+        assert_eq!(fetched.vectors.len(), 1);
+        assert_eq!(fetched.vectors["a"].values, vec![0.5, 0.5]);
+        assert_eq!(fetched.vectors["a"].metadata.as_ref().unwrap()["genre"], "drama");
+        assert_eq!(fetched.vectors["a"].metadata.as_ref().unwrap()["year"], 2002);
+        let page = index.list("", "", 2, None);
+        assert_eq!((page.vectors.len(), page.next_token()), (2, Some("b")));
+        let page = index.list("", "", 2, page.next_token());
+        assert_eq!((page.vectors[0].id.as_str(), page.next_token()), ("c", None));
+
+        index.upsert("other", vec![vector("a", vec![1.0, 1.0], Value::Null)]).unwrap();
+        let drama = index.filtered_stats(Some(&Filter::eq("genre", "drama")));
+        assert_eq!((drama.total_vector_count, drama.namespaces[""].vector_count), (2, 2));
+        assert!(!drama.namespaces.contains_key("other"));
+        index.delete(&DeleteRequest{filter: Some(Filter::eq("genre", "drama")), ..Default::default()}).unwrap();
+        assert_eq!(index.stats().namespaces[""].vector_count, 1);
+        index.delete(&DeleteRequest{ids: Some(vec!["b".to_string()]), ..Default::default()}).unwrap();
+        assert!(!index.stats().namespaces.contains_key(""));
+        index.delete(&DeleteRequest{delete_all: true, namespace: Some("other".to_string()), ..Default::default()}).unwrap();
+        assert_eq!(index.stats().total_vector_count, 0);
+    }
+}
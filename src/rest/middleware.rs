@@ -0,0 +1,396 @@
+//! Hooks run around every request sent by a [`Client`] or an [`Index`], see [`Middleware`].
+//!
+//! Middlewares are added through [`ClientBuilder::middleware`], [`Client::with_middleware`] or
+//! [`Index::with_middleware`]. Every [`Index`] created from a [`Client`] runs the middlewares of
+//! the client, followed by its own.
+//!
+//! [`Client`]: crate::Client
+//! [`Index`]: crate::Index
+//! [`ClientBuilder::middleware`]: crate::ClientBuilder::middleware
+//! [`Client::with_middleware`]: crate::Client::with_middleware
+//! [`Index::with_middleware`]: crate::Index::with_middleware
+
+use std::{fmt, sync::Arc};
+
+use async_trait::async_trait;
+use reqwest::{Method, Request, Response, header::{HeaderMap, HeaderName, HeaderValue}};
+
+use crate::{Error, Result};
+
+/// Describes the request a [`Middleware`] is run for.
+#[derive(Debug, Clone)]
+pub struct RequestContext<'a> {
+    /// The name of the operation, e.g. `query` or `create_index`.
+    pub operation: &'static str,
+    /// The method of the request.
+    pub method: Method,
+    /// The index the request is sent for, if it's sent through an [`Index`].
+    ///
+    /// [`Index`]: crate::Index
+    pub index: Option<&'a str>,
+    /// The namespace targeted by a data plane request.
+    pub namespace: Option<&'a str>,
+    /// The number of vectors sent by an upsert.
+    pub vectors: Option<usize>,
+    /// The number of matches requested by a query.
+    pub top_k: Option<usize>,
+    /// The attempt at sending the request, starting at 1 and increasing with every retry of the
+    /// [`RetryPolicy`].
+    ///
+    /// [`RetryPolicy`]: crate::RetryPolicy
+    pub attempt: u32
+}
+
+/// A hook run around every request sent to Pinecone, for example to add headers, rewrite urls,
+/// log requests or inject failures in tests.
+///
+/// [`Middleware::before_send`] is called in the order the middlewares were added, while
+/// [`Middleware::after_response`] and [`Middleware::on_error`] are called in reverse order, so the
+/// first middleware added sees the request first and the response last. Every attempt of a
+/// retried request runs the middlewares again.
+///
+/// Returning an error from a hook short-circuits the request: the hooks of the remaining
+/// middlewares are skipped, the request isn't sent when the error comes from
+/// [`Middleware::before_send`] and the error is returned to the caller without being retried.
+///
+/// ```
+/// use pinenut::{Error, Result, middleware::{Middleware, RequestContext}};
+///
+/// /// Refuses to delete anything.
+/// struct ReadOnly;
+///
+/// #[async_trait::async_trait]
+/// impl Middleware for ReadOnly {
+///     async fn before_send(&self, context: &RequestContext<'_>, _: &mut reqwest::Request) -> Result<()> {
+///         match context.operation {
+///             "delete" | "delete_index" | "delete_collection" => Err(Error::MiddlewareError("read only".to_string())),
+///             _ => Ok(())
+///         }
+///     }
+/// }
+/// ```
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait Middleware: Send + Sync {
+
+    /// Called before the request is sent, with the request including every header. Returning an
+    /// error prevents the request from being sent.
+    async fn before_send(&self, _context: &RequestContext<'_>, _request: &mut Request) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called once a response is received, whatever its status. Returning an error turns the
+    /// response into that error.
+    async fn after_response(&self, _context: &RequestContext<'_>, _response: &Response) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called when the request fails without a response, or when a middleware returned an error,
+    /// but only if this middleware's [`Middleware::before_send`] ran. Responses with error statuses
+    /// are given to [`Middleware::after_response`] instead.
+    async fn on_error(&self, _context: &RequestContext<'_>, _error: &Error) {}
+}
+
+/// The middlewares of a connection, in the order they were added.
+#[derive(Clone, Default)]
+pub(crate) struct Stack(Vec<Arc<dyn Middleware>>);
+
+impl fmt::Debug for Stack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Stack({} middlewares)", self.0.len())
+    }
+}
+
+impl Stack {
+
+    pub(crate) fn push(&mut self, middleware: Arc<dyn Middleware>) {
+        self.0.push(middleware);
+    }
+
+    /// Sends `request` through `client`, running every middleware around it. On failure, only the
+    /// middlewares whose [`Middleware::before_send`] ran are given the error.
+    pub(crate) async fn send(&self, client: &reqwest::Client, context: &RequestContext<'_>, request: Request) -> Result<Response> {
+        let (result, ran) = self.exchange(client, context, request).await;
+        if let Err(ref err) = result {
+            for middleware in self.0[..ran].iter().rev() {
+                middleware.on_error(context, err).await;
+            }
+        }
+        result
+    }
+
+    /// Returns the result of the exchange and how many middlewares saw the request.
+    async fn exchange(&self, client: &reqwest::Client, context: &RequestContext<'_>, mut request: Request) -> (Result<Response>, usize) {
+        for (i, middleware) in self.0.iter().enumerate() {
+            if let Err(err) = middleware.before_send(context, &mut request).await {
+                return (Err(err), i + 1);
+            }
+        }
+        let ran = self.0.len();
+        let response = match client.execute(request).await {
+            Ok(response) => response,
+            Err(err) => return (Err(Error::ReqwestError(err)), ran)
+        };
+        for middleware in self.0.iter().rev() {
+            if let Err(err) = middleware.after_response(context, &response).await {
+                return (Err(err), ran);
+            }
+        }
+        (Ok(response), ran)
+    }
+}
+
+/// Logs a line for every request, response and error to a sink, [`Logging::stderr`] by default.
+///
+/// Headers are never logged, so neither is the api key. Request bodies are only logged when
+/// enabled with [`Logging::with_bodies`], as they may hold sensitive metadata.
+pub struct Logging {
+    sink: Box<dyn Fn(&str) + Send + Sync>,
+    bodies: bool
+}
+
+impl Logging {
+
+    /// Logs every line by calling `sink`.
+    pub fn new(sink: impl Fn(&str) + Send + Sync + 'static) -> Logging {
+        Logging {
+            sink: Box::new(sink),
+            bodies: false
+        }
+    }
+
+    /// Logs every line to the standard error.
+    pub fn stderr() -> Logging {
+        Logging::new(|line| eprintln!("{}", line))
+    }
+
+    /// Also logs the body of every request.
+    pub fn with_bodies(mut self) -> Logging {
+        self.bodies = true;
+        self
+    }
+}
+
+impl Default for Logging {
+    fn default() -> Self {
+        Logging::stderr()
+    }
+}
+
+impl fmt::Debug for Logging {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Logging").field("bodies", &self.bodies).finish_non_exhaustive()
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl Middleware for Logging {
+    async fn before_send(&self, context: &RequestContext<'_>, request: &mut Request) -> Result<()> {
+        let mut line = format!("--> {} {} ({}, attempt {})", request.method(), request.url(), context.operation, context.attempt);
+        if let Some(body) = request.body().and_then(|body| body.as_bytes()).filter(|_| self.bodies) {
+            line.push(' ');
+            line.push_str(&String::from_utf8_lossy(body));
+        }
+        (self.sink)(&line);
+        Ok(())
+    }
+
+    async fn after_response(&self, context: &RequestContext<'_>, response: &Response) -> Result<()> {
+        (self.sink)(&format!("<-- {} {} ({})", response.status().as_u16(), response.url(), context.operation));
+        Ok(())
+    }
+
+    async fn on_error(&self, context: &RequestContext<'_>, error: &Error) {
+        (self.sink)(&format!("<-- {} failed: {}", context.operation, error));
+    }
+}
+
+/// Sets custom headers on every request, replacing any header of the same name.
+///
+/// ```
+/// use pinenut::middleware::Headers;
+/// use reqwest::header::{HeaderName, HeaderValue};
+///
+/// let headers = Headers::new().header(HeaderName::from_static("x-team"), HeaderValue::from_static("search"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Headers(HeaderMap);
+
+impl Headers {
+
+    /// Creates a middleware without any headers.
+    pub fn new() -> Headers {
+        Headers::default()
+    }
+
+    /// Adds a header to set.
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Headers {
+        self.0.insert(name, value);
+        self
+    }
+}
+
+impl From<HeaderMap> for Headers {
+    fn from(headers: HeaderMap) -> Self {
+        Headers(headers)
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl Middleware for Headers {
+    async fn before_send(&self, _: &RequestContext<'_>, request: &mut Request) -> Result<()> {
+        for (name, value) in &self.0 {
+            request.headers_mut().insert(name.clone(), value.clone());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod middleware_test {
+
+    use std::sync::Mutex;
+
+    use reqwest::StatusCode;
+
+    use super::*;
+    use crate::{
+        models::IndexCreateRequest,
+        rest::{mock::MockPinecone, stub::{Stub, StubResponse}}
+    };
+
+    const STUB_STATS: &str = r#"{"namespaces":{},"dimension":4,"indexFullness":0,"totalVectorCount":0}"#;
+
+    /// Records every hook it runs into a shared log, failing the hooks it is told to.
+    struct Recorder {
+        name: &'static str,
+        log: Arc<Mutex<Vec<String>>>,
+        fail_before: bool,
+        fail_after: bool
+    }
+
+    impl Recorder {
+        fn new(name: &'static str, log: &Arc<Mutex<Vec<String>>>) -> Recorder {
+            Recorder{name, log: log.clone(), fail_before: false, fail_after: false}
+        }
+
+        fn record(&self, hook: &str) {
+            self.log.lock().unwrap().push(format!("{}:{}", self.name, hook));
+        }
+    }
+
+    #[async_trait]
+    impl Middleware for Recorder {
+        async fn before_send(&self, _: &RequestContext<'_>, _: &mut Request) -> Result<()> {
+            self.record("before");
+            match self.fail_before {
+                true => Err(Error::MiddlewareError(self.name.to_string())),
+                false => Ok(())
+            }
+        }
+
+        async fn after_response(&self, _: &RequestContext<'_>, _: &Response) -> Result<()> {
+            self.record("after");
+            match self.fail_after {
+                true => Err(Error::MiddlewareError(self.name.to_string())),
+                false => Ok(())
+            }
+        }
+
+        async fn on_error(&self, _: &RequestContext<'_>, _: &Error) {
+            self.record("error");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ordering() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let stub = Stub::script(vec![StubResponse::new(StatusCode::OK, STUB_STATS)]);
+        let mock = MockPinecone::start().await;
+        let client = mock.client().await.unwrap()
+            .with_middleware(Recorder::new("a", &log))
+            .with_middleware(Recorder::new("b", &log));
+        let index = client.index_by_host("stubbed", stub.url()).with_middleware(Recorder::new("c", &log));
+        index.describe_stats().await.unwrap();
+        assert_eq!(*log.lock().unwrap(), vec!["a:before", "b:before", "c:before", "c:after", "b:after", "a:after"]);
+    }
+
+    #[tokio::test]
+    async fn test_before_send_short_circuits() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let stub = Stub::script(vec![StubResponse::new(StatusCode::OK, STUB_STATS)]);
+        let mock = MockPinecone::start().await;
+        let failing = Recorder{fail_before: true, ..Recorder::new("b", &log)};
+        let client = mock.client().await.unwrap()
+            .with_middleware(Recorder::new("a", &log))
+            .with_middleware(failing)
+            .with_middleware(Recorder::new("c", &log));
+        match client.index_by_host("stubbed", stub.url()).describe_stats().await {
+            Err(Error::MiddlewareError(name)) => assert_eq!(name, "b"),
+            other => panic!("expected a middleware error: {:?}", other)
+        }
+        assert_eq!(*log.lock().unwrap(), vec!["a:before", "b:before", "b:error", "a:error"]);
+        assert_eq!(stub.hits(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_after_response_short_circuits() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let stub = Stub::script(vec![StubResponse::new(StatusCode::OK, STUB_STATS)]);
+        let mock = MockPinecone::start().await;
+        let failing = Recorder{fail_after: true, ..Recorder::new("b", &log)};
+        let client = mock.client().await.unwrap()
+            .with_middleware(Recorder::new("a", &log))
+            .with_middleware(failing);
+        let result = client.index_by_host("stubbed", stub.url()).describe_stats().await;
+        assert!(matches!(result, Err(Error::MiddlewareError(_))));
+        assert_eq!(*log.lock().unwrap(), vec!["a:before", "b:before", "b:after", "b:error", "a:error"]);
+        assert_eq!(stub.hits(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_runs_every_attempt() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let stub = Stub::script(vec![
+            StubResponse::new(StatusCode::SERVICE_UNAVAILABLE, ""),
+            StubResponse::new(StatusCode::OK, STUB_STATS)
+        ]);
+        let mock = MockPinecone::start().await;
+        let client = mock.client().await.unwrap()
+            .with_retry_policy(crate::RetryPolicy{base_delay: std::time::Duration::from_millis(1), ..Default::default()})
+            .with_middleware(Recorder::new("a", &log));
+        client.index_by_host("stubbed", stub.url()).describe_stats().await.unwrap();
+        assert_eq!(*log.lock().unwrap(), vec!["a:before", "a:after", "a:before", "a:after"]);
+    }
+
+    #[tokio::test]
+    async fn test_headers() {
+        let stub = Stub::script(vec![StubResponse::new(StatusCode::OK, STUB_STATS)]);
+        let mock = MockPinecone::start().await;
+        let headers = Headers::new().header(HeaderName::from_static("x-team"), HeaderValue::from_static("search"));
+        let client = mock.client().await.unwrap().with_middleware(headers);
+        client.index_by_host("stubbed", stub.url()).describe_stats().await.unwrap();
+        let request = stub.requests().pop().unwrap();
+        assert_eq!(request.headers["x-team"], "search");
+        assert!(request.headers.contains_key("api-key"));
+    }
+
+    #[tokio::test]
+    async fn test_logging() {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let sink = lines.clone();
+        let mock = MockPinecone::start().await;
+        let client = mock.client().await.unwrap()
+            .with_middleware(Logging::new(move |line| sink.lock().unwrap().push(line.to_string())).with_bodies());
+        client.create_index(IndexCreateRequest::builder("pinenut", 4).build().unwrap()).await.unwrap();
+        let lines = lines.lock().unwrap();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("--> POST "));
+        assert!(lines[0].contains("(create_index, attempt 1)"));
+        assert!(lines[0].contains(r#""name":"pinenut""#));
+        assert!(lines[1].starts_with("<-- 201 "));
+        assert!(lines.iter().all(|line| !line.contains(mock.api_key())));
+    }
+}
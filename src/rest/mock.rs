@@ -0,0 +1,473 @@
+//! An in-process stand-in for Pinecone, enabled by the `mock` feature, to test code built on this
+//! crate without an account or network access.
+//!
+//! [`MockPinecone`] serves the legacy controller (`/actions/whoami`, `/databases` and
+//! `/collections`) and the data plane of every index it holds (upsert, query, fetch, list, update,
+//! delete and describe_index_stats, with or without a filter) from a local http server, keeping
+//! everything in memory. Index descriptions point their host at the same server so that an
+//! [`Index`] needs no extra configuration.
+//!
+//! ```
+//! use pinenut::{mock::MockPinecone, models::{IndexCreateRequest, Vector}};
+//!
+//! #[tokio::main(flavor = "current_thread")]
+//! async fn main() {
+//!     let mock = MockPinecone::start().await;
+//!     let client = mock.client().await.unwrap();
+//!     client.create_index(IndexCreateRequest::builder("movies", 2).build().unwrap()).await.unwrap();
+//!
+//!     let index = client.index("movies");
+//!     let vector = Vector{id: "A".to_string(), values: vec![0.5, 0.5], ..Default::default()};
+//!     let response = index.upsert(String::new(), vec![vector]).await.unwrap();
+//!     assert_eq!(response.upserted_count, 1);
+//! }
+//! ```
+//!
+//! [`Index`]: crate::Index
+
+use std::{
+    collections::BTreeMap,
+    convert::Infallible,
+    net::SocketAddr,
+    sync::{Arc, Mutex}
+};
+
+use hyper::{Body, Request, Response, Server, service::{make_service_fn, service_fn}};
+use reqwest::{Method, StatusCode, Url};
+use serde::{Serialize, de::DeserializeOwned};
+use tokio::task::JoinHandle;
+
+use crate::{Client, ClientBuilder, Error, Result};
+use super::{
+    memory::MemoryIndex,
+    models::{
+        ClientInfo,
+        CollectionDescription,
+        ConfigureIndexRequest,
+        CreateCollectionRequest,
+        DeleteRequest,
+        DescribeIndexStatsRequest,
+        DescribeStatusState,
+        IndexCreateRequest,
+        IndexDatabaseDescription,
+        IndexDescription,
+        IndexStatusDescription,
+        ListRequest,
+        PineconeErrorResponse,
+        QueryRequest,
+        UpdateRequest,
+        VectorRequest
+    }
+};
+
+/// The api key accepted by [`MockPinecone::start`].
+pub const MOCK_API_KEY: &str = "mock-api-key";
+
+/// A local http server emulating Pinecone, see the [module documentation](self).
+///
+/// The server runs on the tokio runtime it was started from and stops when the [`MockPinecone`]
+/// is dropped.
+pub struct MockPinecone {
+    addr: SocketAddr,
+    api_key: String,
+    server: JoinHandle<std::result::Result<(), hyper::Error>>
+}
+
+impl MockPinecone {
+
+    /// Starts a server accepting [`MOCK_API_KEY`].
+    pub async fn start() -> MockPinecone {
+        MockPinecone::with_api_key(MOCK_API_KEY).await
+    }
+
+    /// Starts a server accepting only `api_key`, requests with any other key are answered with
+    /// [`StatusCode::UNAUTHORIZED`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if no local port can be bound.
+    pub async fn with_api_key(api_key: impl Into<String>) -> MockPinecone {
+        let api_key = api_key.into();
+        let state = Arc::new(Mutex::new(State::default()));
+        let served = state.clone();
+        let accepted = api_key.clone();
+        let make_svc = make_service_fn(move |_| {
+            let state = served.clone();
+            let api_key = accepted.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req: Request<Body>| {
+                    let state = state.clone();
+                    let api_key = api_key.clone();
+                    async move {
+                        let authorized = req.headers().get("Api-Key").is_some_and(|key| key == api_key.as_str());
+                        let method = req.method().clone();
+                        let uri = req.uri().clone();
+                        let body = hyper::body::to_bytes(req.into_body()).await?;
+                        let (status, body) = if authorized {
+                            state.lock().unwrap().handle(&method, &uri.to_string(), &body)
+                        } else {
+                            (StatusCode::UNAUTHORIZED, "Invalid API Key".to_string())
+                        };
+                        Ok::<_, hyper::Error>(Response::builder().status(status).body(Body::from(body)).unwrap())
+                    }
+                }))
+            }
+        });
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_svc);
+        let addr = server.local_addr();
+        state.lock().unwrap().url = format!("http://{}", addr);
+        MockPinecone {
+            addr,
+            api_key,
+            server: tokio::spawn(server)
+        }
+    }
+
+    /// The controller url, to be given to [`ClientBuilder::controller_url`].
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// The api key accepted by the server.
+    pub fn api_key(&self) -> &str {
+        &self.api_key
+    }
+
+    /// A [`ClientBuilder`] for the legacy control plane, pointed at the server.
+    pub fn client_builder(&self) -> ClientBuilder {
+        Client::builder(self.api_key.as_str()).controller_url(self.url())
+    }
+
+    /// Builds a [`Client`] connected to the server, see [`MockPinecone::client_builder`].
+    pub async fn client(&self) -> Result<Client> {
+        self.client_builder().build().await
+    }
+}
+
+impl Drop for MockPinecone {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+struct MockIndex {
+    database: IndexDatabaseDescription,
+    data: MemoryIndex
+}
+
+struct MockCollection {
+    description: CollectionDescription,
+    data: MemoryIndex
+}
+
+#[derive(Default)]
+struct State {
+    url: String,
+    indexes: BTreeMap<String, MockIndex>,
+    collections: BTreeMap<String, MockCollection>
+}
+
+type Reply = (StatusCode, String);
+
+/// Replies with Pinecone's json error body.
+fn error(status: StatusCode, code: usize, message: impl Into<String>) -> Reply {
+    let body = PineconeErrorResponse {
+        code,
+        message: message.into(),
+        details: Vec::new()
+    };
+    (status, serde_json::to_string(&body).unwrap())
+}
+
+fn bad_request(message: impl Into<String>) -> Reply {
+    error(StatusCode::BAD_REQUEST, 3, message)
+}
+
+fn not_found(kind: &str, name: &str) -> Reply {
+    error(StatusCode::NOT_FOUND, 5, format!("{} {} not found", kind, name))
+}
+
+fn conflict(kind: &str, name: &str) -> Reply {
+    error(StatusCode::CONFLICT, 6, format!("{} {} already exists", kind, name))
+}
+
+/// Maps an error of the [`MemoryIndex`] to the response Pinecone would give.
+fn invalid(err: Error) -> Reply {
+    bad_request(err.to_string())
+}
+
+fn json(status: StatusCode, value: &impl Serialize) -> Reply {
+    (status, serde_json::to_string(value).unwrap())
+}
+
+fn parse<T: DeserializeOwned>(body: &[u8]) -> std::result::Result<T, Reply> {
+    serde_json::from_slice(body).map_err(|err| bad_request(err.to_string()))
+}
+
+impl State {
+
+    fn handle(&mut self, method: &Method, uri: &str, body: &[u8]) -> Reply {
+        let url = match Url::parse(&format!("{}{}", self.url, uri)) {
+            Ok(url) => url,
+            Err(err) => return bad_request(err.to_string())
+        };
+        let segments: Vec<&str> = url.path().trim_matches('/').split('/').collect();
+        let result = match (method, segments.as_slice()) {
+            (&Method::GET, ["actions", "whoami"]) => Ok(json(StatusCode::OK, &ClientInfo {
+                project_name: "mock".to_string(),
+                user_label: Some("mock".to_string()),
+                user_name: Some("mock".to_string())
+            })),
+            (&Method::GET, ["databases"]) => Ok(json(StatusCode::OK, &self.indexes.keys().collect::<Vec<_>>())),
+            (&Method::POST, ["databases"]) => parse(body).map(|request| self.create_index(request)),
+            (&Method::GET, ["databases", name]) => Ok(self.describe_index(name)),
+            (&Method::PATCH, ["databases", name]) => parse(body).map(|request| self.configure_index(name, request)),
+            (&Method::DELETE, ["databases", name]) => Ok(match self.indexes.remove(*name) {
+                Some(_) => (StatusCode::ACCEPTED, String::new()),
+                None => not_found("index", name)
+            }),
+            (&Method::GET, ["collections"]) => Ok(json(StatusCode::OK, &self.collections.keys().collect::<Vec<_>>())),
+            (&Method::POST, ["collections"]) => parse(body).map(|request| self.create_collection(request)),
+            (&Method::GET, ["collections", name]) => Ok(match self.collections.get(*name) {
+                Some(collection) => json(StatusCode::OK, &collection.description),
+                None => not_found("collection", name)
+            }),
+            (&Method::DELETE, ["collections", name]) => Ok(match self.collections.remove(*name) {
+                Some(_) => (StatusCode::ACCEPTED, String::new()),
+                None => not_found("collection", name)
+            }),
+            (method, ["data", name, path @ ..]) => match self.indexes.get_mut(*name) {
+                Some(index) => data_plane(&mut index.data, method, path, &url, body),
+                None => Ok(not_found("index", name))
+            },
+            _ => Ok(error(StatusCode::NOT_FOUND, 12, format!("{} {} is not implemented", method, url.path())))
+        };
+        result.unwrap_or_else(|reply| reply)
+    }
+
+    fn create_index(&mut self, request: IndexCreateRequest) -> Reply {
+        if let Err(err) = request.validate() {
+            return invalid(err);
+        }
+        if self.indexes.contains_key(&request.name) {
+            return conflict("index", &request.name);
+        }
+        let mut data = MemoryIndex::new(request.dimension, request.metric);
+        if let Some(ref source) = request.source_collection {
+            match self.collections.get(source) {
+                Some(collection) if collection.data.dimension == request.dimension => {
+                    data = collection.data.clone();
+                    data.metric = request.metric;
+                },
+                Some(_) => return bad_request(format!("collection {} has a different dimension", source)),
+                None => return not_found("collection", source)
+            }
+        }
+        let database = IndexDatabaseDescription {
+            name: request.name.clone(),
+            dimension: request.dimension,
+            metric: request.metric,
+            replicas: request.replicas.unwrap_or(1),
+            shards: request.shards.unwrap_or(1),
+            pods: request.pods.unwrap_or(1),
+            pod_type: Some(request.pod_type.unwrap_or_else(|| "p1.x1".to_string()))
+        };
+        self.indexes.insert(request.name, MockIndex{database, data});
+        (StatusCode::CREATED, String::new())
+    }
+
+    fn describe_index(&self, name: &str) -> Reply {
+        let index = match self.indexes.get(name) {
+            Some(index) => index,
+            None => return not_found("index", name)
+        };
+        json(StatusCode::OK, &IndexDescription {
+            database: index.database.clone(),
+            status: IndexStatusDescription {
+                waiting: Vec::new(),
+                crashed: Vec::new(),
+                host: Some(format!("{}/data/{}", self.url, name)),
+                port: Url::parse(&self.url).ok().and_then(|u| u.port()).unwrap_or_default() as usize,
+                state: DescribeStatusState::Ready,
+                ready: true
+            },
+            spec: None
+        })
+    }
+
+    fn configure_index(&mut self, name: &str, request: ConfigureIndexRequest) -> Reply {
+        let index = match self.indexes.get_mut(name) {
+            Some(index) => index,
+            None => return not_found("index", name)
+        };
+        if request.replicas == 0 {
+            return bad_request("replicas must be at least 1");
+        }
+        index.database.replicas = request.replicas;
+        index.database.pod_type = Some(request.pod_type);
+        (StatusCode::ACCEPTED, String::new())
+    }
+
+    fn create_collection(&mut self, request: CreateCollectionRequest) -> Reply {
+        if self.collections.contains_key(&request.name) {
+            return conflict("collection", &request.name);
+        }
+        let data = match self.indexes.get(&request.source) {
+            Some(index) => index.data.clone(),
+            None => return not_found("index", &request.source)
+        };
+        let vector_count = data.stats().total_vector_count as usize;
+        let description = CollectionDescription {
+            name: request.name.clone(),
+            size: vector_count * data.dimension * std::mem::size_of::<f32>(),
+            status: "Ready".to_string(),
+            dimension: Some(data.dimension),
+            vector_count: Some(vector_count),
+            environment: Some("mock".to_string())
+        };
+        self.collections.insert(request.name, MockCollection{description, data});
+        (StatusCode::CREATED, String::new())
+    }
+}
+
+fn data_plane(index: &mut MemoryIndex, method: &Method, path: &[&str], url: &Url, body: &[u8]) -> std::result::Result<Reply, Reply> {
+    let reply = match (method, path) {
+        (&Method::POST, ["vectors", "upsert"]) => {
+            let request: VectorRequest = parse(body)?;
+            match index.upsert(&request.namespace, request.vectors) {
+                Ok(response) => json(StatusCode::OK, &response),
+                Err(err) => invalid(err)
+            }
+        },
+        (&Method::POST, ["query"]) => match index.query(&parse::<QueryRequest>(body)?) {
+            Ok(response) => json(StatusCode::OK, &response),
+            Err(err) => invalid(err)
+        },
+        (&Method::GET, ["vectors", "fetch"]) => {
+            let mut ids = Vec::new();
+            let mut namespace = String::new();
+            for (key, value) in url.query_pairs() {
+                match key.as_ref() {
+                    "ids" => ids.push(value.into_owned()),
+                    "namespace" => namespace = value.into_owned(),
+                    _ => {}
+                }
+            }
+            json(StatusCode::OK, &index.fetch(&ids, &namespace))
+        },
+        (&Method::GET, ["vectors", "list"]) => {
+            let mut request = ListRequest::default();
+            for (key, value) in url.query_pairs() {
+                match key.as_ref() {
+                    "namespace" => request.namespace = Some(value.into_owned()),
+                    "prefix" => request.prefix = Some(value.into_owned()),
+                    "limit" => match value.parse() {
+                        Ok(limit) if limit > 0 => request.limit = Some(limit),
+                        _ => return Err(bad_request("limit must be a positive integer"))
+                    },
+                    "paginationToken" => request.pagination_token = Some(value.into_owned()),
+                    _ => {}
+                }
+            }
+            let page = index.list(
+                request.namespace.as_deref().unwrap_or_default(),
+                request.prefix.as_deref().unwrap_or_default(),
+                request.limit.unwrap_or(100),
+                request.pagination_token.as_deref()
+            );
+            json(StatusCode::OK, &page)
+        },
+        (&Method::POST, ["vectors", "update"]) => {
+            let request: UpdateRequest = parse(body)?;
+            let id = request.id.clone();
+            match index.update(request) {
+                Ok(true) => json(StatusCode::OK, &serde_json::json!({})),
+                Ok(false) => not_found("vector", &id),
+                Err(err) => invalid(err)
+            }
+        },
+        (&Method::POST, ["vectors", "delete"]) => match index.delete(&parse::<DeleteRequest>(body)?) {
+            Ok(()) => json(StatusCode::OK, &serde_json::json!({})),
+            Err(err) => invalid(err)
+        },
+        (&Method::GET, ["describe_index_stats"]) => json(StatusCode::OK, &index.stats()),
+        (&Method::POST, ["describe_index_stats"]) => {
+            let request: DescribeIndexStatsRequest = parse(body)?;
+            json(StatusCode::OK, &index.filtered_stats(request.filter.as_ref()))
+        },
+        _ => error(StatusCode::NOT_FOUND, 12, format!("{} {} is not implemented", method, url.path()))
+    };
+    Ok(reply)
+}
+
+#[cfg(test)]
+mod mock_test {
+
+    use super::*;
+    use serde_json::Value;
+    use crate::{models::{FetchRequest, Filter}, rest::memory::vector};
+
+    #[tokio::test]
+    async fn test_rejects_other_api_keys() {
+        let mock = MockPinecone::start().await;
+        match Client::builder("wrong").controller_url(mock.url()).build().await {
+            Err(Error::Unauthorized(err)) => assert_eq!(err.path, "/actions/whoami"),
+            other => panic!("Expected an unauthorized error: {:?}", other.map(|c| c.info().clone()))
+        }
+        let client = mock.client().await.unwrap();
+        assert_eq!(client.info().project_name, "mock");
+    }
+
+    #[tokio::test]
+    async fn test_data_plane() {
+        let mock = MockPinecone::start().await;
+        let client = mock.client().await.unwrap();
+        client.create_index(IndexCreateRequest::builder("mocked", 2).build().unwrap()).await.unwrap();
+        let index = client.index("mocked");
+        index.upsert("ns".to_string(), vec![vector("a", vec![0.0, 0.0], Value::Null), vector("b", vec![3.0, 4.0], Value::Null)]).await.unwrap();
+
+        let query = QueryRequest{namespace: Some("ns".to_string()), vector: Some(vec![3.0, 3.0]), top_k: 1, ..Default::default()};
+        let response = index.query(query).await.unwrap();
+        assert_eq!(response.matches[0].id, "b");
+        assert_eq!(response.matches[0].score, Some(1.0));
+
+        let fetched = index.fetch(FetchRequest{ids: vec!["a".to_string(), "b".to_string()], namespace: Some("ns".to_string())}).await.unwrap();
+        assert_eq!(fetched.vectors.len(), 2);
+
+        index.delete_vectors(DeleteRequest{ids: Some(vec!["a".to_string()]), namespace: Some("ns".to_string()), ..Default::default()}).await.unwrap();
+        let stats = index.describe_stats().await.unwrap();
+        assert_eq!(stats.total_vector_count, 1);
+        assert_eq!(stats.dimension, 2);
+
+        match index.upsert("ns".to_string(), vec![vector("c", vec![1.0], Value::Null)]).await {
+            Err(Error::InvalidRequest(err)) => {
+                assert_eq!(err.status, StatusCode::BAD_REQUEST);
+                assert!(err.message.contains("dimension"), "{}", err.message);
+                assert_eq!(err.response.map(|r| r.code), Some(3));
+            },
+            other => panic!("Expected a dimension error: {:?}", other)
+        }
+        let filtered = DeleteRequest{filter: Some(Filter::eq("genre", "drama")), namespace: Some("ns".to_string()), ..Default::default()};
+        index.delete_vectors(filtered).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_collections() {
+        let mock = MockPinecone::start().await;
+        let client = mock.client().await.unwrap();
+        client.create_index(IndexCreateRequest::builder("source", 2).build().unwrap()).await.unwrap();
+        client.index("source").upsert(String::new(), vec![vector("a", vec![1.0, 2.0], Value::Null)]).await.unwrap();
+        client.create_collection("backup", "source").await.unwrap();
+        let description = client.describe_collection("backup").await.unwrap();
+        assert_eq!(description.vector_count, Some(1));
+
+        let request = IndexCreateRequest::builder("restored", 2).source_collection("backup").build().unwrap();
+        client.create_index(request).await.unwrap();
+        let restored = client.index("restored");
+        assert_eq!(restored.describe_stats().await.unwrap().total_vector_count, 1);
+
+        client.delete_collection("backup").await.unwrap();
+        assert!(client.list_collections().await.unwrap().is_empty());
+        assert_eq!(client.describe_collection("backup").await.unwrap_err().status(), Some(StatusCode::NOT_FOUND));
+    }
+}
@@ -1,84 +1,252 @@
-use crate::{Error, Result};
+use crate::{Error, Result, error::ResponseError};
 
 mod client;
-pub use client::Client;
+pub use client::{Client, ClientBuilder, ControlPlane};
 
 
 mod index;
 pub use index::Index;
 
+pub mod batch;
+
+pub mod transfer;
+
+pub mod middleware;
+pub use middleware::Middleware;
+
+pub mod credentials;
+pub use credentials::{ApiKey, CredentialsProvider};
+
+pub(crate) mod retry;
+pub use retry::RetryPolicy;
+
+mod wait;
+pub use wait::WaitOptions;
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod stub;
+
+pub(crate) mod memory;
+
+mod local;
+pub use local::LocalIndex;
+
+mod store;
+pub use store::VectorStore;
+
+#[cfg(all(feature = "mock", not(target_arch = "wasm32")))]
+pub mod mock;
+
 pub mod models;
-use models::PineconeErrorResponse;
-use reqwest::{RequestBuilder, Method, StatusCode, Response};
+
+#[cfg(feature = "tracing")]
+mod trace;
+
+use reqwest::{RequestBuilder, Method, StatusCode, Response, header::{HeaderValue, USER_AGENT}};
 use serde::{de::DeserializeOwned, Serialize};
+use std::{fmt, sync::Arc, time::Duration};
 
 /// An abstraction around [`Index`] and [`Client`] created make them work alongside eachother in
 /// [`try_pinecone_get_request`]
 pub(crate) trait Connection {
     fn client(&self) -> &reqwest::Client;
     fn credentials(&self) -> &Credentials;
+    fn config(&self) -> &ConnectionConfig;
+
+    /// The index requests are sent for, recorded in the errors of failed requests.
+    fn index_name(&self) -> Option<&str> {
+        None
+    }
 }
 
-/// Holds the private credentials for a basic pinecone connection. 
+/// Holds the private credentials for a basic pinecone connection, shared by a [`Client`] and
+/// every [`Index`] created from it.
 #[derive(Clone)]
 pub(crate) struct Credentials {
-    pub(crate) api_key: String,
-    pub(crate) environment: String
+    provider: Arc<dyn CredentialsProvider>
+}
+
+impl Credentials {
+    pub(crate) fn new(provider: Arc<dyn CredentialsProvider>) -> Credentials {
+        Credentials{provider}
+    }
+
+    /// The api key to send with the next request.
+    pub(crate) fn api_key(&self) -> Result<ApiKey> {
+        self.provider.api_key()
+    }
+
+    pub(crate) fn environment(&self) -> Option<String> {
+        self.provider.environment()
+    }
+
+    pub(crate) fn control_plane(&self) -> Option<ControlPlane> {
+        self.provider.control_plane()
+    }
+
+    pub(crate) fn controller_url(&self) -> Option<String> {
+        self.provider.controller_url()
+    }
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Credentials")
+    }
+}
+
+/// Settings of a [`Client`] that are shared with every [`Index`] created from it, set through the
+/// [`ClientBuilder`].
+#[derive(Clone)]
+pub(crate) struct ConnectionConfig {
+    pub(crate) control_plane: ControlPlane,
+    /// Base url of the controller, e.g. `https://controller.us-east1-gcp.pinecone.io`.
+    pub(crate) controller_url: String,
+    /// Base url of every index's data plane, where `{index}` is replaced by the index name.
+    pub(crate) data_plane_url: Option<String>,
+    pub(crate) user_agent: Option<String>,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) retry: RetryPolicy,
+    pub(crate) middleware: middleware::Stack
 }
 
+/// Describes what a request is sent for, given to every [`Middleware`] and recorded in its span
+/// when the `tracing` feature is enabled.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Operation<'a> {
+    /// The name of the operation, e.g. `query` or `create_index`.
+    pub(crate) name: &'static str,
+    pub(crate) namespace: Option<&'a str>,
+    /// The number of vectors sent by the request.
+    pub(crate) vectors: Option<usize>,
+    pub(crate) top_k: Option<usize>,
+    /// Whether sending the request twice has a different effect than sending it once, see
+    /// [`RetryPolicy::retry_non_idempotent`].
+    pub(crate) non_idempotent: bool
+}
+
+impl<'a> Operation<'a> {
+    pub(crate) fn new(name: &'static str) -> Self {
+        Operation{name, ..Default::default()}
+    }
+
+    pub(crate) fn namespace(mut self, namespace: Option<&'a str>) -> Self {
+        self.namespace = namespace;
+        self
+    }
+
+    pub(crate) fn vectors(mut self, vectors: usize) -> Self {
+        self.vectors = Some(vectors);
+        self
+    }
+
+    pub(crate) fn top_k(mut self, top_k: usize) -> Self {
+        self.top_k = Some(top_k);
+        self
+    }
+
+    pub(crate) fn non_idempotent(mut self) -> Self {
+        self.non_idempotent = true;
+        self
+    }
+
+    fn context<C: Connection>(&self, con: &'a C, method: &Method, attempt: u32) -> middleware::RequestContext<'a> {
+        middleware::RequestContext {
+            operation: self.name,
+            method: method.clone(),
+            index: con.index_name(),
+            namespace: self.namespace,
+            vectors: self.vectors,
+            top_k: self.top_k,
+            attempt
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
 pub(crate) enum AcceptType {
     Text,
     Json
 }
 
-impl ToString for AcceptType {
-    fn to_string(&self) -> String {
+impl fmt::Display for AcceptType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            AcceptType::Text => String::from("text/plain"),
-            AcceptType::Json => String::from("application/json")
+            AcceptType::Text => write!(f, "text/plain"),
+            AcceptType::Json => write!(f, "application/json")
         }
     }
 }
 
-async fn pinecone_request<T, C>(con: &C, method: Method, accept_type: AcceptType, index_url: Option<impl Into<String>>, path: impl AsRef<str>, data_struct: Option<&T>) -> Result<Response>
+/// Sends a request to pinecone, retrying it according to the connections [`RetryPolicy`]. The
+/// last response is returned once it either isn't retryable or the attempts run out.
+///
+/// With the `tracing` feature every call is wrapped in a span describing `operation`, its outcome
+/// and how often it was retried. Headers, and with them the api key, are never recorded.
+async fn pinecone_request<T, C>(con: &C, operation: Operation<'_>, method: Method, accept_type: AcceptType, index_url: Option<impl Into<String>>, path: impl AsRef<str>, data_struct: Option<&T>) -> Result<Response>
 where
     C: Connection,
     T: Serialize
 {
-    let request;
-    if let Some(url) = index_url {
-        request = url_base_request(con, method.clone(), accept_type, url, path);
-    } else {
-        request = base_request(con, method.clone(), accept_type, path);
-    }
-    let response;
-    match method {
-        Method::DELETE => {
-            response = request 
-                .send()
-                .await;
-        },
+    let data = match method {
+        Method::DELETE | Method::GET => None,
         Method::POST | Method::PATCH => {
-            let data;
             match data_struct {
-                Some(ref val) => {data=val;},
+                Some(val) => Some(val),
                 None => return Err(Error::ArgumentError {name: "data_struct".to_string(), found: "None".to_string(), expected: "a valuec".to_string()})
             }
-            response = request
-                .json(data)
-                .send()
-                .await;
-        },
-        Method::GET => {
-            response = request 
-                .send()
-                .await;
         },
         method => return Err(Error::UnsupportedMethod{method})
-    }
-    match response {
-        Ok(resp) => Ok(resp),
-        Err(err) => Err(Error::ReqwestError(err))
+    };
+    let index_url: Option<String> = index_url.map(Into::into);
+    let policy = &con.config().retry;
+    let send = async {
+        let mut attempt = 1;
+        loop {
+            let request = match index_url {
+                Some(ref url) => url_base_request(con, method.clone(), accept_type, url.as_str(), path.as_ref()),
+                None => base_request(con, method.clone(), accept_type, path.as_ref())
+            };
+            let mut request = match request {
+                Ok(request) => request,
+                Err(err) => return (Err(err), attempt - 1)
+            };
+            if let Some(data) = data {
+                request = request.json(data);
+            }
+            let request = match request.build() {
+                Ok(request) => request,
+                Err(err) => return (Err(Error::ReqwestError(err)), attempt - 1)
+            };
+            let retrying = attempt < policy.max_attempts;
+            let context = operation.context(con, &method, attempt);
+            let delay = match con.config().middleware.send(con.client(), &context, request).await {
+                Ok(resp) if retrying && policy.retries_status(resp.status(), !operation.non_idempotent) => policy.delay(attempt, Some(resp.headers())),
+                Ok(resp) => return (Ok(resp), attempt - 1),
+                Err(Error::ReqwestError(ref err)) if retrying && policy.retries_error(err, !operation.non_idempotent) => policy.delay(attempt, None),
+                Err(err) => return (Err(err), attempt - 1)
+            };
+            #[cfg(feature = "tracing")]
+            tracing::debug!(attempt, delay_ms = delay.as_millis() as u64, "retrying request");
+            retry::sleep(delay).await;
+            attempt += 1;
+        }
+    };
+    #[cfg(feature = "tracing")]
+    return trace::instrument(con, operation, &method, send).await;
+    #[cfg(not(feature = "tracing"))]
+    send.await.0
+}
+
+/// Turns an error response into the [`Error`] matching its status, recording the request that
+/// caused it.
+async fn response_error<C: Connection>(con: &C, method: Method, resp: Response) -> Error {
+    let status = resp.status();
+    let path = resp.url().path().to_string();
+    let retry_after = retry::retry_after(resp.headers());
+    match resp.text().await {
+        Ok(body) => ResponseError::new(status, method, path, con.index_name().map(String::from), body, retry_after).into_error(),
+        Err(err) => Error::ReqwestResponseError(status, err)
     }
 }
 
@@ -88,16 +256,16 @@ where
 ///
 /// This function should be called using the turbofish syntax unless the compiler can figure out
 /// the type of S. S is a model / struct that represnts a successfull post request. If this
-/// function fails it will attempt to construct a [`Error::PineconeResponseError`] using the
-/// [`PineconeErrorResponse`], if it fails to do so it will return a normal
-/// [`Error::ReqwestError`]
-pub(crate) async fn try_pinecone_request_json<C, T, S>(con: &C, method: Method, success_code: StatusCode, index_url: Option<impl Into<String>>, path: impl AsRef<str>, data_struct: Option<&T>) -> Result<S> 
+/// function fails with an error status it returns the [`Error`] classifying that status, such as
+/// [`Error::NotFound`], holding a [`ResponseError`]. A successfull response that can't be decoded
+/// returns an [`Error::ReqwestResponseError`].
+pub(crate) async fn try_pinecone_request_json<C, T, S>(con: &C, operation: Operation<'_>, method: Method, success_code: StatusCode, index_url: Option<impl Into<String>>, path: impl AsRef<str>, data_struct: Option<&T>) -> Result<S> 
 where
     C: Connection,
     T: Serialize,
     S: DeserializeOwned,
 {
-    let resp = pinecone_request(con, method, AcceptType::Json, index_url, path, data_struct).await?;
+    let resp = pinecone_request(con, operation, method.clone(), AcceptType::Json, index_url, path, data_struct).await?;
     let code = resp.status();
     if resp.status() == success_code {
         match resp.json::<S>().await {
@@ -106,18 +274,15 @@ where
         }
         
     }
-    match resp.json::<PineconeErrorResponse>().await {
-        Ok(f) => Err(Error::PineconeResponseError(code, Some(f), None)),
-        Err(err) => Err(Error::ReqwestResponseError(code, err))
-    }
+    Err(response_error(con, method, resp).await)
 }
 
-pub(crate) async fn try_pinecone_request_text<C, T>(con: &C, method: Method, success_code: StatusCode, index_url: Option<impl Into<String>>, path: impl AsRef<str>, data_struct: Option<&T>) -> Result<String> 
+pub(crate) async fn try_pinecone_request_text<C, T>(con: &C, operation: Operation<'_>, method: Method, success_code: StatusCode, index_url: Option<impl Into<String>>, path: impl AsRef<str>, data_struct: Option<&T>) -> Result<String> 
 where
     C: Connection,
     T: Serialize
 {
-    let resp = pinecone_request(con, method, AcceptType::Text, index_url, path, data_struct).await?;
+    let resp = pinecone_request(con, operation, method.clone(), AcceptType::Text, index_url, path, data_struct).await?;
     let code = resp.status();
     if resp.status() == success_code {
         match resp.text().await {
@@ -126,35 +291,47 @@ where
         }
         
     }
-    match resp.text().await {
-        Ok(f) => Err(Error::PineconeResponseError(code,None, Some(f))),
-        Err(err) => Err(Error::ReqwestResponseError(code, err))
-    }
+    Err(response_error(con, method, resp).await)
 }
 
 
 /// Creates a basic incomplete request to pinecone and populates it with the api key and the
 /// accepted type
-pub(crate) fn base_request<C, A>(con: &C, method: Method, accept_type: AcceptType, path: A) -> RequestBuilder 
+pub(crate) fn base_request<C, A>(con: &C, method: Method, accept_type: AcceptType, path: A) -> Result<RequestBuilder>
 where
     C: Connection,
     A: AsRef<str>
 {
-    con.client().request(method, format!("https://controller.{}.pinecone.io{}", con.credentials().environment, path.as_ref()))
-        .header("Api-Key", &con.credentials().api_key)
-        .header("accept", accept_type.to_string())
-        .header("content-type", "application/json")
+    url_base_request(con, method, accept_type, con.config().controller_url.as_str(), path)
 }
 
 
-pub(crate) fn url_base_request<C, U, A>(con: &C, method: Method, accept_type: AcceptType, url: U, path: A) -> RequestBuilder 
+pub(crate) fn url_base_request<C, U, A>(con: &C, method: Method, accept_type: AcceptType, url: U, path: A) -> Result<RequestBuilder>
 where
     C: Connection,
     U: Into<String>,
     A: AsRef<str>
 {
-    con.client().request(method, format!("{}{}", url.into(), path.as_ref()))
-        .header("Api-Key", &con.credentials().api_key)
+    let config = con.config();
+    let mut api_key = HeaderValue::from_str(con.credentials().api_key()?.expose()).map_err(|_| Error::ArgumentError {
+        name: "api_key".to_string(),
+        found: "a key with characters not allowed in a header".to_string(),
+        expected: "a visible ascii key".to_string()
+    })?;
+    api_key.set_sensitive(true);
+    let mut request = con.client().request(method, format!("{}{}", url.into(), path.as_ref()))
+        .header("Api-Key", api_key)
         .header("accept", accept_type.to_string())
-        .header("content-type", "application/json")
+        .header("content-type", "application/json");
+    if config.control_plane == ControlPlane::Global {
+        request = request.header("X-Pinecone-API-Version", ControlPlane::GLOBAL_API_VERSION);
+    }
+    if let Some(ref user_agent) = config.user_agent {
+        request = request.header(USER_AGENT, user_agent);
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(timeout) = config.timeout {
+        request = request.timeout(timeout);
+    }
+    Ok(request)
 }
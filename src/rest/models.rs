@@ -9,8 +9,10 @@ use std::collections::{HashMap, BTreeMap};
 
 use serde::{Serialize, Deserialize};
 
+use crate::{Error, Result};
+
 /// The distance metric used for similarity search.
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum Metric {
     /// Euclidian Distance.
     #[serde(rename="euclidean")]
@@ -74,14 +76,307 @@ pub struct PineconeErrorResponse {
 }
 
 /// Request sent to pinecone for the creation of an Index.
+///
+/// Usually created through [`IndexCreateRequest::builder`], which validates the request. The
+/// pod settings (`pods`, `replicas`, `shards`, `pod_type`, `metadata_config` and
+/// `source_collection`) are those of the legacy control plane, the global control plane takes
+/// them through [`IndexSpec::Pod`] instead.
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct IndexCreateRequest {
     /// Index name.
     pub name: String,
     /// The dimension for the vectors stored within the index.
     pub dimension: usize,
-    /// The metric for the Index, all the options for this are detailed in [`Metric`].
-    pub metric: String
+    /// The metric for the Index.
+    pub metric: Metric,
+    /// Number of pods for the index.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pods: Option<usize>,
+    /// Number of replicas for the index.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replicas: Option<usize>,
+    /// Number of shards for the index.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shards: Option<usize>,
+    /// The type of pod. One of s1, p1, or p2 appended with . and one of x1, x2, x4, or x8.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pod_type: Option<String>,
+    /// Which metadata fields are indexed, every field is indexed when none is given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata_config: Option<MetadataConfig>,
+    /// Name of the collection to create the index from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_collection: Option<String>,
+    /// Where the index is deployed. This is required by, and only supported by, the
+    /// [`ControlPlane::Global`] control plane.
+    ///
+    /// [`ControlPlane::Global`]: crate::ControlPlane::Global
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spec: Option<IndexSpec>
+}
+
+/// The longest index name Pinecone accepts.
+const MAX_INDEX_NAME_LENGTH: usize = 45;
+
+/// The largest vector dimension Pinecone accepts.
+const MAX_DIMENSION: usize = 20000;
+
+fn argument_error(name: &str, found: impl fmt::Debug, expected: &str) -> Error {
+    Error::ArgumentError {
+        name: name.to_string(),
+        found: format!("{:?}", found),
+        expected: expected.to_string()
+    }
+}
+
+fn validate_count(name: &str, count: Option<usize>) -> Result<()> {
+    match count {
+        Some(0) => Err(argument_error(name, 0, "at least 1")),
+        _ => Ok(())
+    }
+}
+
+fn validate_pod_type(pod_type: &str) -> Result<()> {
+    let valid = match pod_type.split_once('.') {
+        Some((kind, size)) => ["s1", "p1", "p2"].contains(&kind) && ["x1", "x2", "x4", "x8"].contains(&size),
+        None => false
+    };
+    if !valid {
+        return Err(argument_error("pod_type", pod_type, "one of s1, p1 or p2 followed by . and one of x1, x2, x4 or x8"));
+    }
+    Ok(())
+}
+
+fn validate_metadata_config(config: &MetadataConfig) -> Result<()> {
+    if config.indexed.iter().any(String::is_empty) {
+        return Err(argument_error("metadata_config", &config.indexed, "non empty field names"));
+    }
+    Ok(())
+}
+
+impl IndexCreateRequest {
+
+    /// Returns an [`IndexCreateRequestBuilder`] for an index with the given name and dimension.
+    pub fn builder(name: impl Into<String>, dimension: usize) -> IndexCreateRequestBuilder {
+        IndexCreateRequestBuilder {
+            request: IndexCreateRequest {
+                name: name.into(),
+                dimension,
+                ..Default::default()
+            }
+        }
+    }
+
+    /// Checks the request against the limits Pinecone puts on index creation, this is done by
+    /// [`Client::create_index`] before the request is sent.
+    ///
+    /// # Error
+    ///
+    /// Returns an [`Error::ArgumentError`] naming the first invalid field.
+    ///
+    /// [`Client::create_index`]: crate::Client::create_index
+    pub fn validate(&self) -> Result<()> {
+        let name_valid = !self.name.is_empty()
+            && self.name.len() <= MAX_INDEX_NAME_LENGTH
+            && self.name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+            && !self.name.starts_with('-')
+            && !self.name.ends_with('-');
+        if !name_valid {
+            return Err(argument_error("name", &self.name, "1 to 45 lowercase alphanumeric characters or '-', starting and ending with an alphanumeric character"));
+        }
+        if self.dimension == 0 || self.dimension > MAX_DIMENSION {
+            return Err(argument_error("dimension", self.dimension, "between 1 and 20000"));
+        }
+        validate_count("pods", self.pods)?;
+        validate_count("replicas", self.replicas)?;
+        validate_count("shards", self.shards)?;
+        if let Some(ref pod_type) = self.pod_type {
+            validate_pod_type(pod_type)?;
+        }
+        if let Some(ref config) = self.metadata_config {
+            validate_metadata_config(config)?;
+        }
+        if let Some(IndexSpec::Pod(ref pod)) = self.spec {
+            validate_count("pods", pod.pods)?;
+            validate_count("replicas", pod.replicas)?;
+            validate_count("shards", pod.shards)?;
+            validate_pod_type(&pod.pod_type)?;
+            if let Some(ref config) = pod.metadata_config {
+                validate_metadata_config(config)?;
+            }
+        }
+        if self.spec.is_some() && self.has_pod_settings() {
+            return Err(argument_error("spec", &self.spec, "pod settings within IndexSpec::Pod rather than the request when a spec is given"));
+        }
+        Ok(())
+    }
+
+    /// Whether any of the legacy control plane pod settings are set.
+    pub(crate) fn has_pod_settings(&self) -> bool {
+        self.pods.is_some()
+            || self.replicas.is_some()
+            || self.shards.is_some()
+            || self.pod_type.is_some()
+            || self.metadata_config.is_some()
+            || self.source_collection.is_some()
+    }
+}
+
+/// Builds and validates an [`IndexCreateRequest`], created through [`IndexCreateRequest::builder`].
+///
+/// ```
+/// use pinenut::models::{IndexCreateRequest, Metric};
+///
+/// let request = IndexCreateRequest::builder("movies", 768)
+///     .metric(Metric::COSINE)
+///     .pods(2)
+///     .replicas(2)
+///     .pod_type("p1.x2")
+///     .metadata_config(["genre", "year"])
+///     .build()
+///     .unwrap();
+/// assert_eq!(request.replicas, Some(2));
+/// ```
+#[derive(Debug, Clone)]
+pub struct IndexCreateRequestBuilder {
+    request: IndexCreateRequest
+}
+
+impl IndexCreateRequestBuilder {
+
+    /// The similarity metric, [`Metric::EUCLIDEAN`] by default.
+    pub fn metric(mut self, metric: Metric) -> IndexCreateRequestBuilder {
+        self.request.metric = metric;
+        self
+    }
+
+    /// Number of pods for the index.
+    pub fn pods(mut self, pods: usize) -> IndexCreateRequestBuilder {
+        self.request.pods = Some(pods);
+        self
+    }
+
+    /// Number of replicas for the index.
+    pub fn replicas(mut self, replicas: usize) -> IndexCreateRequestBuilder {
+        self.request.replicas = Some(replicas);
+        self
+    }
+
+    /// Number of shards for the index.
+    pub fn shards(mut self, shards: usize) -> IndexCreateRequestBuilder {
+        self.request.shards = Some(shards);
+        self
+    }
+
+    /// The type of pod, such as `p1.x1`.
+    pub fn pod_type(mut self, pod_type: impl Into<String>) -> IndexCreateRequestBuilder {
+        self.request.pod_type = Some(pod_type.into());
+        self
+    }
+
+    /// Only indexes the given metadata fields.
+    pub fn metadata_config<I, S>(mut self, indexed: I) -> IndexCreateRequestBuilder
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>
+    {
+        self.request.metadata_config = Some(MetadataConfig{indexed: indexed.into_iter().map(Into::into).collect()});
+        self
+    }
+
+    /// Creates the index from the given collection.
+    pub fn source_collection(mut self, collection: impl Into<String>) -> IndexCreateRequestBuilder {
+        self.request.source_collection = Some(collection.into());
+        self
+    }
+
+    /// Deploys the index with the given spec, required by the global control plane.
+    pub fn spec(mut self, spec: IndexSpec) -> IndexCreateRequestBuilder {
+        self.request.spec = Some(spec);
+        self
+    }
+
+    /// Validates and returns the [`IndexCreateRequest`], see [`IndexCreateRequest::validate`].
+    pub fn build(self) -> Result<IndexCreateRequest> {
+        self.request.validate()?;
+        Ok(self.request)
+    }
+}
+
+/// The deployment of an index on the global control plane, either serverless or pod based.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum IndexSpec {
+    /// A serverless index, scaled and billed by usage.
+    #[serde(rename="serverless")]
+    Serverless(ServerlessSpec),
+    /// A pod based index.
+    #[serde(rename="pod")]
+    Pod(PodSpec)
+}
+
+/// The cloud provider hosting a serverless index.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub enum Cloud {
+    /// Amazon Web Services.
+    #[serde(rename="aws")]
+    #[default]
+    AWS,
+    /// Google Cloud Platform.
+    #[serde(rename="gcp")]
+    GCP,
+    /// Microsoft Azure.
+    #[serde(rename="azure")]
+    AZURE
+}
+
+impl fmt::Display for Cloud {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cloud::AWS => write!(f, "aws"),
+            Cloud::GCP => write!(f, "gcp"),
+            Cloud::AZURE => write!(f, "azure")
+        }
+    }
+}
+
+/// Configuration of a serverless index.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct ServerlessSpec {
+    /// The cloud the index is hosted in.
+    pub cloud: Cloud,
+    /// The region of the cloud, such as `us-east-1`.
+    pub region: String
+}
+
+/// Configuration of a pod based index.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct PodSpec {
+    /// The environment the index is hosted in, such as `us-east1-gcp`.
+    pub environment: String,
+    /// The type of pod. One of s1, p1, or p2 appended with . and one of x1, x2, x4, or x8.
+    pub pod_type: String,
+    /// Number of pods.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pods: Option<usize>,
+    /// Number of replicas.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replicas: Option<usize>,
+    /// Number of shards.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shards: Option<usize>,
+    /// Which metadata fields are indexed, every field is indexed when none is given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata_config: Option<MetadataConfig>,
+    /// Name of the collection the index is created from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_collection: Option<String>
+}
+
+/// Selects the metadata fields that are indexed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct MetadataConfig {
+    /// The indexed metadata fields, all other fields are stored but can't be filtered on.
+    pub indexed: Vec<String>
 }
 
 /// Details information about an individual namespace, found in [`IndexStats`].
@@ -118,6 +413,15 @@ pub struct IndexStats {
     pub total_vector_count: u32
 }
 
+/// Requests the [`IndexStats`] of only the vectors matching a metadata filter.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct DescribeIndexStatsRequest {
+    /// Only vectors matching this metadata filter are counted. See [Metadata
+    /// Filtering](https://www.pinecone.io/docs/metadata-filtering/)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<Filter>
+}
+
 /// Vector sparse data. Represented as a list of indeices and a list of corresponded values, which
 /// must be the same length.
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
@@ -141,6 +445,206 @@ pub struct FetchResponse{
 /// better understood their implementations might be transfered to a more type strict version.
 pub type MappedValue = BTreeMap<String, serde_json::Value>;
 
+/// The comparison operators Pinecone supports within a metadata [`Filter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterOperator {
+    /// Equal to, `$eq`.
+    Eq,
+    /// Not equal to, `$ne`.
+    Ne,
+    /// Greater than, `$gt`.
+    Gt,
+    /// Greater than or equal to, `$gte`.
+    Gte,
+    /// Less than, `$lt`.
+    Lt,
+    /// Less than or equal to, `$lte`.
+    Lte,
+    /// Within a list of values, `$in`.
+    In,
+    /// Not within a list of values, `$nin`.
+    Nin,
+    /// The field exists (or does not exist), `$exists`.
+    Exists
+}
+
+impl fmt::Display for FilterOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterOperator::Eq => write!(f, "$eq"),
+            FilterOperator::Ne => write!(f, "$ne"),
+            FilterOperator::Gt => write!(f, "$gt"),
+            FilterOperator::Gte => write!(f, "$gte"),
+            FilterOperator::Lt => write!(f, "$lt"),
+            FilterOperator::Lte => write!(f, "$lte"),
+            FilterOperator::In => write!(f, "$in"),
+            FilterOperator::Nin => write!(f, "$nin"),
+            FilterOperator::Exists => write!(f, "$exists")
+        }
+    }
+}
+
+/// A metadata filter, serialized to Pinecone's [filter
+/// syntax](https://www.pinecone.io/docs/metadata-filtering/).
+///
+/// Filters are built from conditions on single fields and can be composed with [`Filter::and`] and
+/// [`Filter::or`]. A raw map can still be given through [`Filter::Raw`] (or `From<MappedValue>`)
+/// for anything the builder does not cover.
+///
+/// ```
+/// use pinenut::models::Filter;
+///
+/// let filter = Filter::eq("genre", "drama").and(Filter::gte("year", 2019));
+/// assert_eq!(
+///     serde_json::to_string(&filter).unwrap(),
+///     r#"{"$and":[{"genre":{"$eq":"drama"}},{"year":{"$gte":2019}}]}"#
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    /// A single condition applied to a metadata field.
+    Condition {
+        /// The metadata field.
+        field: String,
+        /// The comparison operator.
+        operator: FilterOperator,
+        /// The value compared against.
+        value: serde_json::Value
+    },
+    /// Every filter must match, `$and`.
+    And(Vec<Filter>),
+    /// At least one filter must match, `$or`.
+    Or(Vec<Filter>),
+    /// A raw filter sent to Pinecone as is.
+    Raw(MappedValue)
+}
+
+impl Filter {
+
+    fn condition(field: impl Into<String>, operator: FilterOperator, value: serde_json::Value) -> Filter {
+        Filter::Condition {
+            field: field.into(),
+            operator,
+            value
+        }
+    }
+
+    /// The field is equal to `value`.
+    pub fn eq(field: impl Into<String>, value: impl Into<serde_json::Value>) -> Filter {
+        Filter::condition(field, FilterOperator::Eq, value.into())
+    }
+
+    /// The field is not equal to `value`.
+    pub fn ne(field: impl Into<String>, value: impl Into<serde_json::Value>) -> Filter {
+        Filter::condition(field, FilterOperator::Ne, value.into())
+    }
+
+    /// The field is greater than `value`.
+    pub fn gt(field: impl Into<String>, value: impl Into<serde_json::Value>) -> Filter {
+        Filter::condition(field, FilterOperator::Gt, value.into())
+    }
+
+    /// The field is greater than or equal to `value`.
+    pub fn gte(field: impl Into<String>, value: impl Into<serde_json::Value>) -> Filter {
+        Filter::condition(field, FilterOperator::Gte, value.into())
+    }
+
+    /// The field is less than `value`.
+    pub fn lt(field: impl Into<String>, value: impl Into<serde_json::Value>) -> Filter {
+        Filter::condition(field, FilterOperator::Lt, value.into())
+    }
+
+    /// The field is less than or equal to `value`.
+    pub fn lte(field: impl Into<String>, value: impl Into<serde_json::Value>) -> Filter {
+        Filter::condition(field, FilterOperator::Lte, value.into())
+    }
+
+    /// The field is equal to one of `values`.
+    pub fn is_in<V>(field: impl Into<String>, values: impl IntoIterator<Item = V>) -> Filter
+    where
+        V: Into<serde_json::Value>
+    {
+        Filter::condition(field, FilterOperator::In, values.into_iter().map(Into::into).collect())
+    }
+
+    /// The field is equal to none of `values`.
+    pub fn not_in<V>(field: impl Into<String>, values: impl IntoIterator<Item = V>) -> Filter
+    where
+        V: Into<serde_json::Value>
+    {
+        Filter::condition(field, FilterOperator::Nin, values.into_iter().map(Into::into).collect())
+    }
+
+    /// The field exists when `exists` is true, or is missing when false.
+    pub fn exists(field: impl Into<String>, exists: bool) -> Filter {
+        Filter::condition(field, FilterOperator::Exists, exists.into())
+    }
+
+    /// Combines this filter with `other`, both of which must match. Chained calls are flattened
+    /// into a single `$and`.
+    pub fn and(self, other: Filter) -> Filter {
+        match self {
+            Filter::And(mut filters) => {
+                filters.push(other);
+                Filter::And(filters)
+            },
+            filter => Filter::And(vec![filter, other])
+        }
+    }
+
+    /// Combines this filter with `other`, one of which must match. Chained calls are flattened
+    /// into a single `$or`.
+    pub fn or(self, other: Filter) -> Filter {
+        match self {
+            Filter::Or(mut filters) => {
+                filters.push(other);
+                Filter::Or(filters)
+            },
+            filter => Filter::Or(vec![filter, other])
+        }
+    }
+
+    /// Returns the json representation of the filter sent to Pinecone.
+    pub fn to_value(&self) -> serde_json::Value {
+        match self {
+            Filter::Condition{field, operator, value} => {
+                serde_json::json!({ field.as_str(): { operator.to_string(): value } })
+            },
+            Filter::And(filters) => {
+                serde_json::json!({ "$and": filters.iter().map(Filter::to_value).collect::<Vec<_>>() })
+            },
+            Filter::Or(filters) => {
+                serde_json::json!({ "$or": filters.iter().map(Filter::to_value).collect::<Vec<_>>() })
+            },
+            Filter::Raw(map) => serde_json::to_value(map).unwrap_or_default()
+        }
+    }
+}
+
+impl From<MappedValue> for Filter {
+    fn from(value: MappedValue) -> Self {
+        Filter::Raw(value)
+    }
+}
+
+impl Serialize for Filter {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer
+    {
+        self.to_value().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Filter {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>
+    {
+        MappedValue::deserialize(deserializer).map(Filter::Raw)
+    }
+}
+
 /// Updates a vector in a namespace.
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct UpdateRequest {
@@ -159,6 +663,41 @@ pub struct UpdateRequest {
     pub namespace: Option<String>,
 }
 
+/// Deletes vectors, by id, from a single namespace. Exactly one of [`DeleteRequest::ids`],
+/// [`DeleteRequest::delete_all`] or [`DeleteRequest::filter`] should be given.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct DeleteRequest {
+    /// Vector ids to delete.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ids: Option<Vec<String>>,
+    /// Deletes all the vectors in the namespace when true.
+    #[serde(rename="deleteAll", default)]
+    pub delete_all: bool,
+    /// Namespace to delete the vectors from, the default namespace is used if none is given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    /// Deletes the vectors matching this metadata filter. See [Metadata
+    /// Filtering](https://www.pinecone.io/docs/metadata-filtering/)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<Filter>,
+}
+
+impl DeleteRequest {
+
+    /// Checks that the request targets exactly one of ids, the whole namespace or a filter.
+    pub(crate) fn validate(&self) -> Result<()> {
+        let targets = [self.ids.is_some(), self.delete_all, self.filter.is_some()];
+        if targets.iter().filter(|t| **t).count() != 1 {
+            return Err(Error::ArgumentError {
+                name: "DeleteRequest".to_string(),
+                found: format!("ids: {}, delete_all: {}, filter: {}", self.ids.is_some(), self.delete_all, self.filter.is_some()),
+                expected: "exactly one of ids, delete_all or filter".to_string()
+            });
+        }
+        Ok(())
+    }
+}
+
 /// Represents a vector that can be sent and retrieved from pinecone.
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct Vector {
@@ -182,7 +721,7 @@ pub struct QueryRequest {
     pub top_k: usize, 
     /// The filter to apply. You can use vector metadata to limit your search. See [Metadat
     /// Filtering](https://www.pinecone.io/docs/metadata-filtering/)
-    pub filter: Option<BTreeMap<String, serde_json::Value>>,
+    pub filter: Option<Filter>,
     /// Whether vector values should be included in the response
     #[serde(rename="includeValues")]
     pub include_values: bool,
@@ -235,19 +774,139 @@ pub struct FetchRequest {
 
 impl FetchRequest {
 
-    pub(crate) fn url(self, base: impl Into<String>) -> String {
-        let mut url: String = base.into();
-        url.push_str("/vectors/fetch?");
-        for id in self.ids {
-            url += format!("ids={}&", id).as_ref();
+    /// The url of the request, ids and namespace are percent encoded so that any id can be
+    /// fetched.
+    pub(crate) fn url(self, base: impl Into<String>) -> Result<String> {
+        let mut url = data_plane_url(base.into(), "/vectors/fetch")?;
+        {
+            let mut query = url.query_pairs_mut();
+            for id in self.ids {
+                query.append_pair("ids", &id);
+            }
+            if let Some(namespace) = self.namespace {
+                query.append_pair("namespace", &namespace);
+            }
+        }
+        Ok(url.to_string())
+    }
+}
+
+/// Joins a data plane base url and the path of an operation.
+fn data_plane_url(base: String, path: &str) -> Result<reqwest::Url> {
+    let url = format!("{}{}", base, path);
+    reqwest::Url::parse(&url).map_err(|err| Error::ArgumentError {
+        name: "url".to_string(),
+        found: format!("{} ({})", url, err),
+        expected: "a valid data plane url".to_string()
+    })
+}
+
+/// Lists the ids of the vectors within a namespace, one page at a time. Ids are returned in order.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct ListRequest {
+    /// Namespace to list the ids of, the default namespace is used if none is given.
+    pub namespace: Option<String>,
+    /// Only ids starting with this prefix are listed.
+    pub prefix: Option<String>,
+    /// Maximum number of ids within the page, Pinecone defaults to 100.
+    pub limit: Option<usize>,
+    /// The [`Pagination::next`] token of the previous page, the first page is listed if none is
+    /// given.
+    #[serde(rename = "paginationToken")]
+    pub pagination_token: Option<String>
+}
+
+impl ListRequest {
+
+    /// Checks that the request asks for at least one id per page.
+    pub(crate) fn validate(&self) -> Result<()> {
+        if self.limit == Some(0) {
+            return Err(Error::ArgumentError {
+                name: "limit".to_string(),
+                found: "0".to_string(),
+                expected: "a page size greater than 0".to_string()
+            });
         }
-        if let Some(namespace) = self.namespace {
-            url += format!("namespace={}", namespace).as_ref();
-        } else {
-            url.truncate(url.len() - 1);
+        Ok(())
+    }
+
+    /// The first request of a listing of `namespace` following [`ListOptions`].
+    pub(crate) fn first(namespace: String, prefix: Option<String>, options: ListOptions) -> ListRequest {
+        ListRequest {
+            namespace: Some(namespace),
+            prefix,
+            limit: options.page_size,
+            pagination_token: options.pagination_token
         }
-        url
     }
+
+    pub(crate) fn url(&self, base: impl Into<String>) -> Result<String> {
+        let mut url = data_plane_url(base.into(), "/vectors/list")?;
+        {
+            let mut query = url.query_pairs_mut();
+            if let Some(ref namespace) = self.namespace {
+                query.append_pair("namespace", namespace);
+            }
+            if let Some(ref prefix) = self.prefix {
+                query.append_pair("prefix", prefix);
+            }
+            if let Some(limit) = self.limit {
+                query.append_pair("limit", &limit.to_string());
+            }
+            if let Some(ref token) = self.pagination_token {
+                query.append_pair("paginationToken", token);
+            }
+        }
+        Ok(url.to_string())
+    }
+}
+
+/// Options of [`Index::list_ids`].
+///
+/// [`Index::list_ids`]: crate::Index::list_ids
+#[derive(Debug, Default, Clone)]
+pub struct ListOptions {
+    /// Number of ids requested at a time, Pinecone defaults to 100.
+    pub page_size: Option<usize>,
+    /// Resumes a previous listing from the [`Pagination::next`] token of one of its pages.
+    pub pagination_token: Option<String>
+}
+
+/// A page of ids returned for a [`ListRequest`].
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct ListResponse {
+    /// The ids of the page.
+    #[serde(default)]
+    pub vectors: Vec<ListItem>,
+    /// Holds the token of the next page, none once the last page is reached.
+    pub pagination: Option<Pagination>,
+    /// The namespace that was listed.
+    #[serde(default)]
+    pub namespace: String
+}
+
+impl ListResponse {
+
+    /// The token of the next page, if there is one.
+    pub fn next_token(&self) -> Option<&str> {
+        self.pagination.as_ref()
+            .and_then(|p| p.next.as_deref())
+            .filter(|next| !next.is_empty())
+    }
+}
+
+/// An id within a [`ListResponse`].
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct ListItem {
+    /// The vector id.
+    pub id: String
+}
+
+/// Pagination details of a [`ListResponse`].
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct Pagination {
+    /// Token to pass as [`ListRequest::pagination_token`] to request the next page.
+    pub next: Option<String>
 }
 
 /// Returns information about the index in great depth.
@@ -256,7 +915,79 @@ pub struct IndexDescription {
     /// Specific Index Information.
     pub database: IndexDatabaseDescription,
     /// Current status of the index.
-    pub status: IndexStatusDescription 
+    pub status: IndexStatusDescription,
+    /// Deployment of the index, only given by the global control plane.
+    #[serde(default)]
+    pub spec: Option<IndexSpec>
+}
+
+/// An index as described by the global control plane, converted into an [`IndexDescription`].
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub(crate) struct GlobalIndexDescription {
+    pub(crate) name: String,
+    pub(crate) dimension: usize,
+    pub(crate) metric: Metric,
+    pub(crate) host: Option<String>,
+    pub(crate) spec: Option<IndexSpec>,
+    pub(crate) status: GlobalIndexStatus
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub(crate) struct GlobalIndexStatus {
+    pub(crate) ready: bool,
+    pub(crate) state: DescribeStatusState
+}
+
+impl From<GlobalIndexDescription> for IndexDescription {
+    fn from(value: GlobalIndexDescription) -> Self {
+        let pod = match value.spec {
+            Some(IndexSpec::Pod(ref pod)) => Some(pod),
+            _ => None
+        };
+        IndexDescription {
+            database: IndexDatabaseDescription {
+                name: value.name,
+                dimension: value.dimension,
+                metric: value.metric,
+                replicas: pod.and_then(|p| p.replicas).unwrap_or_default(),
+                shards: pod.and_then(|p| p.shards).unwrap_or_default(),
+                pods: pod.and_then(|p| p.pods).unwrap_or_default(),
+                pod_type: pod.map(|p| p.pod_type.clone())
+            },
+            status: IndexStatusDescription {
+                waiting: Vec::new(),
+                crashed: Vec::new(),
+                host: value.host,
+                port: 443,
+                state: value.status.state,
+                ready: value.status.ready
+            },
+            spec: value.spec
+        }
+    }
+}
+
+/// The list of indexes returned by the global control plane.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub(crate) struct GlobalIndexList {
+    pub(crate) indexes: Vec<GlobalIndexDescription>
+}
+
+/// The list of collections returned by the global control plane.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub(crate) struct GlobalCollectionList {
+    pub(crate) collections: Vec<CollectionDescription>
+}
+
+/// Request sent to the global control plane to change the settings of a pod based index.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub(crate) struct GlobalConfigureIndexRequest {
+    pub(crate) spec: GlobalConfigureSpec
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub(crate) struct GlobalConfigureSpec {
+    pub(crate) pod: ConfigureIndexRequest
 }
 
 /// Details the configuration of the index.
@@ -315,6 +1046,10 @@ pub enum DescribeStatusState {
     ScalingUp,
     /// ScalingDown.
     ScalingDown,
+    /// Scaling up the size of the pods.
+    ScalingUpPodSize,
+    /// Scaling down the size of the pods.
+    ScalingDownPodSize,
     /// Terminating.
     Terminating,
     /// Ready.
@@ -330,6 +1065,8 @@ impl fmt::Display for DescribeStatusState {
             DescribeStatusState::Initializing => write!(f, "Initializing"),
             DescribeStatusState::ScalingUp => write!(f, "ScalingUp"),
             DescribeStatusState::ScalingDown => write!(f, "ScalingDown"),
+            DescribeStatusState::ScalingUpPodSize => write!(f, "ScalingUpPodSize"),
+            DescribeStatusState::ScalingDownPodSize => write!(f, "ScalingDownPodSize"),
             DescribeStatusState::Terminating => write!(f, "Terminating"),
             DescribeStatusState::Ready => write!(f, "Ready"),
             DescribeStatusState::InitializationFailed => write!(f, "InitializationFailed")
@@ -343,9 +1080,19 @@ pub struct CollectionDescription {
     /// Collection name.
     pub name: String,
     /// Collection size.
+    #[serde(default)]
     pub size: usize,
     /// Status of the collection.
-    pub status: String
+    pub status: String,
+    /// Dimension of the vectors within the collection, only given by the global control plane.
+    #[serde(default)]
+    pub dimension: Option<usize>,
+    /// Number of vectors within the collection, only given by the global control plane.
+    #[serde(default)]
+    pub vector_count: Option<usize>,
+    /// Environment the collection is stored in, only given by the global control plane.
+    #[serde(default)]
+    pub environment: Option<String>
 }
 
 /// Response from an upsert request sending data to the Index.
@@ -355,3 +1102,150 @@ pub struct UpsertResponse {
     #[serde(rename = "upsertedCount")]
     pub upserted_count: usize
 }
+
+#[cfg(test)]
+mod models_test {
+
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_filter_operators() {
+        let filters = vec![
+            (Filter::eq("a", "x"), json!({"a": {"$eq": "x"}})),
+            (Filter::ne("a", 1), json!({"a": {"$ne": 1}})),
+            (Filter::gt("a", 1.5), json!({"a": {"$gt": 1.5}})),
+            (Filter::gte("a", 2), json!({"a": {"$gte": 2}})),
+            (Filter::lt("a", 3), json!({"a": {"$lt": 3}})),
+            (Filter::lte("a", 4), json!({"a": {"$lte": 4}})),
+            (Filter::is_in("a", ["x", "y"]), json!({"a": {"$in": ["x", "y"]}})),
+            (Filter::not_in("a", [1, 2]), json!({"a": {"$nin": [1, 2]}})),
+            (Filter::exists("a", true), json!({"a": {"$exists": true}})),
+        ];
+        for (filter, expected) in filters {
+            assert_eq!(serde_json::to_value(&filter).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_filter_composition() {
+        let filter = Filter::eq("genre", "drama")
+            .and(Filter::gte("year", 2019))
+            .and(Filter::eq("lang", "en").or(Filter::eq("lang", "fr")));
+        assert_eq!(serde_json::to_value(&filter).unwrap(), json!({
+            "$and": [
+                {"genre": {"$eq": "drama"}},
+                {"year": {"$gte": 2019}},
+                {"$or": [{"lang": {"$eq": "en"}}, {"lang": {"$eq": "fr"}}]}
+            ]
+        }));
+    }
+
+    #[test]
+    fn test_filter_raw() {
+        let mut raw = MappedValue::new();
+        raw.insert("genre".to_string(), json!({"$eq": "drama"}));
+        let filter = Filter::from(raw.clone());
+        assert_eq!(serde_json::to_value(&filter).unwrap(), json!({"genre": {"$eq": "drama"}}));
+
+        let parsed: Filter = serde_json::from_value(json!({"genre": {"$eq": "drama"}})).unwrap();
+        assert_eq!(parsed, Filter::Raw(raw));
+    }
+
+    #[test]
+    fn test_index_spec() {
+        let serverless = IndexSpec::Serverless(ServerlessSpec{cloud: Cloud::AWS, region: "us-east-1".to_string()});
+        assert_eq!(serde_json::to_value(&serverless).unwrap(), json!({"serverless": {"cloud": "aws", "region": "us-east-1"}}));
+
+        let pod = IndexSpec::Pod(PodSpec{environment: "us-east1-gcp".to_string(), pod_type: "p1.x1".to_string(), pods: Some(1), ..Default::default()});
+        assert_eq!(serde_json::to_value(&pod).unwrap(), json!({"pod": {"environment": "us-east1-gcp", "pod_type": "p1.x1", "pods": 1}}));
+    }
+
+    #[test]
+    fn test_global_index_description() {
+        let global: GlobalIndexDescription = serde_json::from_value(json!({
+            "name": "pods",
+            "dimension": 8,
+            "metric": "dotproduct",
+            "host": "pods-abc.svc.us-east1-gcp.pinecone.io",
+            "spec": {"pod": {"environment": "us-east1-gcp", "pod_type": "p1.x2", "pods": 2, "replicas": 3, "shards": 1}},
+            "status": {"ready": false, "state": "ScalingUpPodSize"},
+            "deletion_protection": "disabled"
+        })).unwrap();
+        let description = IndexDescription::from(global);
+        assert_eq!(description.database.dimension, 8);
+        assert_eq!(description.database.replicas, 3);
+        assert_eq!(description.database.pod_type.as_deref(), Some("p1.x2"));
+        assert_eq!(description.status.host.as_deref(), Some("pods-abc.svc.us-east1-gcp.pinecone.io"));
+        assert_eq!(description.status.state.to_string(), "ScalingUpPodSize");
+        assert!(matches!(description.spec, Some(IndexSpec::Pod(_))));
+    }
+
+    #[test]
+    fn test_index_create_request_builder() {
+        let request = IndexCreateRequest::builder("movies", 768)
+            .metric(Metric::DOTPRODUCT)
+            .pods(4)
+            .replicas(2)
+            .shards(2)
+            .pod_type("s1.x4")
+            .metadata_config(["genre"])
+            .source_collection("movies-backup")
+            .build()
+            .unwrap();
+        assert_eq!(serde_json::to_value(&request).unwrap(), json!({
+            "name": "movies",
+            "dimension": 768,
+            "metric": "dotproduct",
+            "pods": 4,
+            "replicas": 2,
+            "shards": 2,
+            "pod_type": "s1.x4",
+            "metadata_config": {"indexed": ["genre"]},
+            "source_collection": "movies-backup"
+        }));
+
+        let minimal = IndexCreateRequest::builder("movies", 8).build().unwrap();
+        assert_eq!(serde_json::to_value(&minimal).unwrap(), json!({"name": "movies", "dimension": 8, "metric": "euclidean"}));
+    }
+
+    #[test]
+    fn test_index_create_request_validation() {
+        let invalid = vec![
+            ("name", IndexCreateRequest::builder("Movies", 8)),
+            ("name", IndexCreateRequest::builder("-movies", 8)),
+            ("name", IndexCreateRequest::builder("m".repeat(46), 8)),
+            ("dimension", IndexCreateRequest::builder("movies", 0)),
+            ("dimension", IndexCreateRequest::builder("movies", 20001)),
+            ("pods", IndexCreateRequest::builder("movies", 8).pods(0)),
+            ("replicas", IndexCreateRequest::builder("movies", 8).replicas(0)),
+            ("pod_type", IndexCreateRequest::builder("movies", 8).pod_type("p3.x1")),
+            ("pod_type", IndexCreateRequest::builder("movies", 8).pod_type("p1")),
+            ("metadata_config", IndexCreateRequest::builder("movies", 8).metadata_config([""])),
+            ("pod_type", IndexCreateRequest::builder("movies", 8).spec(IndexSpec::Pod(PodSpec{environment: "env".to_string(), pod_type: "x1".to_string(), ..Default::default()}))),
+            ("spec", IndexCreateRequest::builder("movies", 8).replicas(1).spec(IndexSpec::Serverless(ServerlessSpec::default()))),
+        ];
+        for (field, builder) in invalid {
+            match builder.build() {
+                Err(Error::ArgumentError{name, ..}) => assert_eq!(name, field),
+                other => panic!("expected {} to be invalid: {:?}", field, other)
+            }
+        }
+    }
+
+    #[test]
+    fn test_query_request_filter() {
+        let request = QueryRequest{top_k: 1, filter: Some(Filter::is_in("tag", ["a"])), ..Default::default()};
+        let value = serde_json::to_value(&request).unwrap();
+        assert_eq!(value["filter"], json!({"tag": {"$in": ["a"]}}));
+    }
+
+    #[test]
+    fn test_data_plane_urls() {
+        let fetch = FetchRequest{ids: vec!["doc#1".to_string(), "a b".to_string()], namespace: Some("ns".to_string())};
+        assert_eq!(fetch.url("http://localhost:1").unwrap(), "http://localhost:1/vectors/fetch?ids=doc%231&ids=a+b&namespace=ns");
+        let list = ListRequest{prefix: Some("doc#".to_string()), limit: Some(5), ..Default::default()};
+        assert_eq!(list.url("http://localhost:1").unwrap(), "http://localhost:1/vectors/list?prefix=doc%23&limit=5");
+        assert!(matches!(ListRequest::default().url("not a url"), Err(Error::ArgumentError{..})));
+    }
+}
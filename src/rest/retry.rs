@@ -0,0 +1,317 @@
+use std::time::Duration;
+
+use reqwest::{StatusCode, header::{HeaderMap, RETRY_AFTER}};
+
+/// Decides when and how often a failed request to Pinecone is sent again.
+///
+/// A [`RetryPolicy`] is configured on the [`Client`] and inherited by every [`Index`] created from
+/// it. Requests are retried when Pinecone responds with one of [`RetryPolicy::retry_statuses`] or
+/// when sending fails with one of the enabled `reqwest` error kinds. The delay between attempts
+/// grows exponentially from [`RetryPolicy::base_delay`] up to [`RetryPolicy::max_delay`], unless
+/// Pinecone sends a `Retry-After` header which is used instead, capped at the same maximum.
+///
+/// Operations creating a resource, such as creating an index or a collection, aren't idempotent:
+/// sending one again after its success response was lost fails with [`Error::AlreadyExists`]. By
+/// default these are only retried when Pinecone certainly didn't process them, after a `429` or a
+/// failed connection, see [`RetryPolicy::retry_non_idempotent`].
+///
+/// [`Client`]: crate::Client
+/// [`Index`]: crate::Index
+/// [`Error::AlreadyExists`]: crate::Error::AlreadyExists
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one. A value of 1 disables retries.
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled on each following retry.
+    pub base_delay: Duration,
+    /// Upper bound on the delay between two attempts.
+    pub max_delay: Duration,
+    /// Randomizes each delay to between half and all of its value so that many clients don't
+    /// retry in lockstep.
+    pub jitter: bool,
+    /// Response status codes that are retried.
+    pub retry_statuses: Vec<StatusCode>,
+    /// Retry requests that timed out.
+    pub retry_timeouts: bool,
+    /// Retry requests that failed to connect.
+    pub retry_connect_errors: bool,
+    /// Retry requests that failed while being sent or read, such as a reset connection.
+    pub retry_request_errors: bool,
+    /// Use the `Retry-After` header, in seconds, as the delay when Pinecone sends one, up to
+    /// [`RetryPolicy::max_delay`].
+    pub respect_retry_after: bool,
+    /// Also retry operations that aren't idempotent on server errors, timeouts and failed
+    /// requests, which may have been processed already.
+    pub retry_non_idempotent: bool
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+            jitter: true,
+            retry_statuses: vec![
+                StatusCode::TOO_MANY_REQUESTS,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                StatusCode::BAD_GATEWAY,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::GATEWAY_TIMEOUT
+            ],
+            retry_timeouts: true,
+            retry_connect_errors: true,
+            retry_request_errors: true,
+            respect_retry_after: true,
+            retry_non_idempotent: false
+        }
+    }
+}
+
+impl RetryPolicy {
+
+    /// A policy that sends every request exactly once.
+    pub fn none() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Whether a response with the given status should be retried. A rate limited request wasn't
+    /// processed, so it's retried even if it isn't idempotent.
+    pub(crate) fn retries_status(&self, status: StatusCode, idempotent: bool) -> bool {
+        self.retry_statuses.contains(&status)
+            && (idempotent || self.retry_non_idempotent || status == StatusCode::TOO_MANY_REQUESTS)
+    }
+
+    /// Whether a request that failed with the given error should be retried. A request that
+    /// failed to connect never reached Pinecone, so it's retried even if it isn't idempotent.
+    pub(crate) fn retries_error(&self, err: &reqwest::Error, idempotent: bool) -> bool {
+        #[cfg(not(target_arch = "wasm32"))]
+        if err.is_connect() {
+            return self.retry_connect_errors;
+        }
+        if !idempotent && !self.retry_non_idempotent {
+            return false;
+        }
+        if err.is_timeout() {
+            return self.retry_timeouts;
+        }
+        err.is_request() && self.retry_request_errors
+    }
+
+    /// Returns how long to wait before sending the attempt following `attempt`, where the first
+    /// attempt is 1.
+    pub(crate) fn delay(&self, attempt: u32, headers: Option<&HeaderMap>) -> Duration {
+        if self.respect_retry_after {
+            if let Some(retry_after) = headers.and_then(retry_after) {
+                return retry_after.min(self.max_delay);
+            }
+        }
+        let exponent = attempt.saturating_sub(1).min(31);
+        let delay = self.base_delay.saturating_mul(1 << exponent).min(self.max_delay);
+        if self.jitter {
+            let half = delay / 2;
+            return half + half.mul_f64(fastrand::f64());
+        }
+        delay
+    }
+}
+
+/// Parses a `Retry-After` header given in seconds. The http-date form is not supported and is
+/// ignored.
+pub(crate) fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers.get(RETRY_AFTER)?
+        .to_str().ok()?
+        .trim()
+        .parse::<u64>().ok()
+        .map(Duration::from_secs)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) async fn sleep(duration: Duration) {
+    gloo_timers::future::sleep(duration).await
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod retry_test {
+
+    use super::*;
+    use std::time::Instant;
+    use reqwest::Method;
+    use serde_json::Value;
+    use crate::{Error, rest::{ApiKey, Connection, ConnectionConfig, Credentials, Operation, try_pinecone_request_json, stub::{Stub, StubResponse}}};
+
+    struct StubConnection {
+        client: reqwest::Client,
+        creds: Credentials,
+        config: ConnectionConfig
+    }
+
+    impl Connection for StubConnection {
+        fn client(&self) -> &reqwest::Client {
+            &self.client
+        }
+        fn credentials(&self) -> &Credentials {
+            &self.creds
+        }
+        fn config(&self) -> &ConnectionConfig {
+            &self.config
+        }
+    }
+
+    fn connection(retry: RetryPolicy) -> StubConnection {
+        StubConnection {
+            client: reqwest::Client::new(),
+            creds: Credentials::new(std::sync::Arc::new(ApiKey::new("key"))),
+            config: ConnectionConfig {
+                control_plane: Default::default(),
+                controller_url: "http://127.0.0.1:1".to_string(),
+                data_plane_url: None,
+                user_agent: None,
+                timeout: None,
+                retry,
+                middleware: Default::default()
+            }
+        }
+    }
+
+    fn fast_policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+            ..Default::default()
+        }
+    }
+
+    async fn get(con: &StubConnection, stub: &Stub) -> crate::Result<Value> {
+        try_pinecone_request_json::<StubConnection, String, Value>(con, Operation::new("describe_index_stats"), Method::GET, StatusCode::OK, Some(stub.url()), "/describe_index_stats", None).await
+    }
+
+    #[tokio::test]
+    async fn test_retries_until_success() {
+        let stub = Stub::script(vec![
+            StubResponse::new(StatusCode::SERVICE_UNAVAILABLE, ""),
+            StubResponse::new(StatusCode::TOO_MANY_REQUESTS, ""),
+            StubResponse::new(StatusCode::OK, "{}")
+        ]);
+        let value = get(&connection(fast_policy(3)), &stub).await.unwrap();
+        assert_eq!(value, serde_json::json!({}));
+        assert_eq!(stub.hits(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_attempts() {
+        let stub = Stub::script(vec![StubResponse::new(StatusCode::SERVICE_UNAVAILABLE, "unavailable")]);
+        match get(&connection(fast_policy(4)), &stub).await {
+            Err(err @ Error::ServerError(_)) => {
+                assert_eq!(err.status(), Some(StatusCode::SERVICE_UNAVAILABLE));
+                assert!(err.is_retryable());
+            },
+            other => panic!("expected a 503 error: {:?}", other)
+        }
+        assert_eq!(stub.hits(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_does_not_retry_other_statuses() {
+        let stub = Stub::script(vec![StubResponse::new(StatusCode::BAD_REQUEST, r#"{"code":3,"message":"bad","details":[]}"#)]);
+        match get(&connection(fast_policy(3)), &stub).await {
+            Err(Error::InvalidRequest(err)) => {
+                assert_eq!(err.message, "bad");
+                assert_eq!(err.path, "/describe_index_stats");
+            },
+            other => panic!("expected a 400 error: {:?}", other)
+        }
+        assert_eq!(stub.hits(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_none_policy() {
+        let stub = Stub::script(vec![StubResponse::new(StatusCode::SERVICE_UNAVAILABLE, "")]);
+        assert!(get(&connection(RetryPolicy::none()), &stub).await.is_err());
+        assert_eq!(stub.hits(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_honors_retry_after() {
+        let stub = Stub::script(vec![
+            StubResponse::new(StatusCode::TOO_MANY_REQUESTS, "").header("retry-after", "1"),
+            StubResponse::new(StatusCode::OK, "{}")
+        ]);
+        let start = Instant::now();
+        get(&connection(RetryPolicy{max_delay: Duration::from_secs(2), ..fast_policy(2)}), &stub).await.unwrap();
+        assert!(start.elapsed() >= Duration::from_secs(1));
+        assert_eq!(stub.hits(), 2);
+    }
+
+    async fn create(con: &StubConnection, stub: &Stub) -> crate::Result<String> {
+        crate::rest::try_pinecone_request_text::<StubConnection, Value>(con, Operation::new("create_collection").non_idempotent(), Method::POST, StatusCode::CREATED, Some(stub.url()), "/collections", Some(&serde_json::json!({}))).await
+    }
+
+    #[tokio::test]
+    async fn test_non_idempotent_operations() {
+        let stub = Stub::script(vec![
+            StubResponse::new(StatusCode::TOO_MANY_REQUESTS, ""),
+            StubResponse::new(StatusCode::SERVICE_UNAVAILABLE, ""),
+            StubResponse::new(StatusCode::CREATED, "")
+        ]);
+        assert!(matches!(create(&connection(fast_policy(3)), &stub).await, Err(Error::ServerError(_))));
+        assert_eq!(stub.hits(), 2);
+
+        let stub = Stub::script(vec![
+            StubResponse::new(StatusCode::SERVICE_UNAVAILABLE, ""),
+            StubResponse::new(StatusCode::CREATED, "")
+        ]);
+        create(&connection(RetryPolicy{retry_non_idempotent: true, ..fast_policy(2)}), &stub).await.unwrap();
+        assert_eq!(stub.hits(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_retries_connect_errors() {
+        let con = connection(RetryPolicy {
+            max_attempts: 2,
+            base_delay: Duration::from_millis(200),
+            jitter: false,
+            ..Default::default()
+        });
+        let start = Instant::now();
+        let result = try_pinecone_request_json::<StubConnection, String, Value>(&con, Operation::new("describe_index_stats"), Method::GET, StatusCode::OK, Some("http://127.0.0.1:1"), "/describe_index_stats", None).await;
+        assert!(matches!(result, Err(Error::ReqwestError(_))));
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[test]
+    fn test_delay() {
+        let policy = RetryPolicy {
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(350),
+            jitter: false,
+            ..Default::default()
+        };
+        assert_eq!(policy.delay(1, None), Duration::from_millis(100));
+        assert_eq!(policy.delay(2, None), Duration::from_millis(200));
+        assert_eq!(policy.delay(3, None), Duration::from_millis(350));
+        assert_eq!(policy.delay(40, None), Duration::from_millis(350));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, "7".parse().unwrap());
+        assert_eq!(policy.delay(1, Some(&headers)), Duration::from_millis(350));
+        let patient = RetryPolicy{max_delay: Duration::from_secs(10), ..policy.clone()};
+        assert_eq!(patient.delay(1, Some(&headers)), Duration::from_secs(7));
+
+        let jittered = RetryPolicy{jitter: true, ..policy};
+        for attempt in 1..5 {
+            let delay = jittered.delay(attempt, None);
+            let max = jittered.max_delay.min(jittered.base_delay * (1 << (attempt - 1)));
+            assert!(delay >= max / 2 && delay <= max);
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::Result;
+use super::{
+    Index,
+    LocalIndex,
+    models::{
+        DeleteRequest,
+        FetchRequest,
+        FetchResponse,
+        IndexStats,
+        QueryRequest,
+        QueryResponse,
+        UpdateRequest,
+        UpsertResponse,
+        Vector
+    }
+};
+
+/// The data plane operations of an index, implemented by [`Index`] and [`LocalIndex`].
+///
+/// Code written against [`VectorStore`] rather than a concrete index can switch between hosted
+/// and local storage, or be given a test double or a decorator wrapping another store, such as
+/// one adding caching or metrics. The trait is object safe, so stores can be chosen at runtime as
+/// a `Box<dyn VectorStore>`. On wasm the returned futures aren't [`Send`].
+///
+/// ```
+/// use pinenut::{LocalIndex, VectorStore, models::{Metric, Vector}};
+///
+/// async fn store_greeting(store: &dyn VectorStore) -> pinenut::Result<usize> {
+///     let vector = Vector{id: "hello".to_string(), values: vec![1.0, 0.0], ..Default::default()};
+///     Ok(store.upsert(String::new(), vec![vector]).await?.upserted_count)
+/// }
+///
+/// # async fn run() -> pinenut::Result<()> {
+/// let store: Box<dyn VectorStore> = Box::new(LocalIndex::new(2, Metric::COSINE));
+/// assert_eq!(store_greeting(store.as_ref()).await?, 1);
+/// # Ok(())
+/// # }
+/// ```
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait VectorStore {
+
+    /// Inserts or replaces `vectors` within `namespace`.
+    async fn upsert(&self, namespace: String, vectors: Vec<Vector>) -> Result<UpsertResponse>;
+
+    /// Returns the vectors of a namespace most similar to the query vector or id.
+    async fn query(&self, request: QueryRequest) -> Result<QueryResponse>;
+
+    /// Looks up vectors of a namespace by id.
+    async fn fetch(&self, request: FetchRequest) -> Result<FetchResponse>;
+
+    /// Updates the values or metadata of a single vector.
+    async fn update(&self, request: UpdateRequest) -> Result<Value>;
+
+    /// Deletes vectors by id, by metadata filter or every vector of a namespace. This is named
+    /// after [`Index::delete_vectors`] as [`Index::delete`] deletes the index itself.
+    async fn delete_vectors(&self, request: DeleteRequest) -> Result<Value>;
+
+    /// Returns the current statistics of the store.
+    async fn describe_stats(&self) -> Result<IndexStats>;
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl VectorStore for Index {
+
+    async fn upsert(&self, namespace: String, vectors: Vec<Vector>) -> Result<UpsertResponse> {
+        Index::upsert(self, namespace, vectors).await
+    }
+
+    async fn query(&self, request: QueryRequest) -> Result<QueryResponse> {
+        Index::query(self, request).await
+    }
+
+    async fn fetch(&self, request: FetchRequest) -> Result<FetchResponse> {
+        Index::fetch(self, request).await
+    }
+
+    async fn update(&self, request: UpdateRequest) -> Result<Value> {
+        Index::update(self, request).await
+    }
+
+    async fn delete_vectors(&self, request: DeleteRequest) -> Result<Value> {
+        Index::delete_vectors(self, request).await
+    }
+
+    /// Requests the latest statistics, without updating those cached by
+    /// [`Index::describe_stats`].
+    async fn describe_stats(&self) -> Result<IndexStats> {
+        self.fetch_stats().await
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl VectorStore for LocalIndex {
+
+    async fn upsert(&self, namespace: String, vectors: Vec<Vector>) -> Result<UpsertResponse> {
+        LocalIndex::upsert(self, namespace, vectors).await
+    }
+
+    async fn query(&self, request: QueryRequest) -> Result<QueryResponse> {
+        LocalIndex::query(self, request).await
+    }
+
+    async fn fetch(&self, request: FetchRequest) -> Result<FetchResponse> {
+        LocalIndex::fetch(self, request).await
+    }
+
+    async fn update(&self, request: UpdateRequest) -> Result<Value> {
+        LocalIndex::update(self, request).await
+    }
+
+    async fn delete_vectors(&self, request: DeleteRequest) -> Result<Value> {
+        LocalIndex::delete_vectors(self, request).await
+    }
+
+    async fn describe_stats(&self) -> Result<IndexStats> {
+        LocalIndex::describe_stats(self).await
+    }
+}
+
+/// Forwards every operation of a pointer type to the store it points to.
+macro_rules! forward_store {
+    ($($pointer:ident),*) => {$(
+        #[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+        #[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+        impl<S> VectorStore for $pointer<S>
+        where
+            S: VectorStore + ?Sized + Send + Sync
+        {
+            async fn upsert(&self, namespace: String, vectors: Vec<Vector>) -> Result<UpsertResponse> {
+                (**self).upsert(namespace, vectors).await
+            }
+
+            async fn query(&self, request: QueryRequest) -> Result<QueryResponse> {
+                (**self).query(request).await
+            }
+
+            async fn fetch(&self, request: FetchRequest) -> Result<FetchResponse> {
+                (**self).fetch(request).await
+            }
+
+            async fn update(&self, request: UpdateRequest) -> Result<Value> {
+                (**self).update(request).await
+            }
+
+            async fn delete_vectors(&self, request: DeleteRequest) -> Result<Value> {
+                (**self).delete_vectors(request).await
+            }
+
+            async fn describe_stats(&self) -> Result<IndexStats> {
+                (**self).describe_stats().await
+            }
+        }
+    )*}
+}
+
+forward_store!(Box, Arc);
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod store_test {
+
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use crate::{mock::MockPinecone, models::{IndexCreateRequest, Metric}};
+
+    /// A decorator counting the queries sent to the store it wraps.
+    struct CountingStore<S> {
+        inner: S,
+        queries: AtomicUsize
+    }
+
+    #[async_trait]
+    impl<S: VectorStore + Send + Sync> VectorStore for CountingStore<S> {
+        async fn upsert(&self, namespace: String, vectors: Vec<Vector>) -> Result<UpsertResponse> {
+            self.inner.upsert(namespace, vectors).await
+        }
+        async fn query(&self, request: QueryRequest) -> Result<QueryResponse> {
+            self.queries.fetch_add(1, Ordering::SeqCst);
+            self.inner.query(request).await
+        }
+        async fn fetch(&self, request: FetchRequest) -> Result<FetchResponse> {
+            self.inner.fetch(request).await
+        }
+        async fn update(&self, request: UpdateRequest) -> Result<Value> {
+            self.inner.update(request).await
+        }
+        async fn delete_vectors(&self, request: DeleteRequest) -> Result<Value> {
+            self.inner.delete_vectors(request).await
+        }
+        async fn describe_stats(&self) -> Result<IndexStats> {
+            self.inner.describe_stats().await
+        }
+    }
+
+    /// Exercises a store only through the trait, the way application code would.
+    async fn exercise(store: &dyn VectorStore) {
+        let vectors = vec![
+            Vector{id: "a".to_string(), values: vec![1.0, 0.0], ..Default::default()},
+            Vector{id: "b".to_string(), values: vec![0.0, 1.0], ..Default::default()}
+        ];
+        assert_eq!(store.upsert("ns".to_string(), vectors).await.unwrap().upserted_count, 2);
+        let query = QueryRequest{namespace: Some("ns".to_string()), vector: Some(vec![0.9, 0.1]), top_k: 1, ..Default::default()};
+        assert_eq!(store.query(query).await.unwrap().matches[0].id, "a");
+        store.update(UpdateRequest{id: "b".to_string(), values: Some(vec![1.0, 1.0]), namespace: Some("ns".to_string()), ..Default::default()}).await.unwrap();
+        let fetched = store.fetch(FetchRequest{ids: vec!["b".to_string()], namespace: Some("ns".to_string())}).await.unwrap();
+        assert_eq!(fetched.vectors["b"].values, vec![1.0, 1.0]);
+        store.delete_vectors(DeleteRequest{ids: Some(vec!["a".to_string()]), namespace: Some("ns".to_string()), ..Default::default()}).await.unwrap();
+        assert_eq!(store.describe_stats().await.unwrap().total_vector_count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_backends() {
+        exercise(&LocalIndex::new(2, Metric::COSINE)).await;
+
+        let mock = MockPinecone::start().await;
+        let client = mock.client().await.unwrap();
+        client.create_index(IndexCreateRequest::builder("store", 2).metric(Metric::COSINE).build().unwrap()).await.unwrap();
+        let index = client.index("store");
+        exercise(&index).await;
+        assert!(index.stats().is_none(), "the trait doesn't touch the cached stats");
+    }
+
+    #[tokio::test]
+    async fn test_decorator() {
+        let store = CountingStore {
+            inner: Arc::new(LocalIndex::new(2, Metric::COSINE)),
+            queries: AtomicUsize::new(0)
+        };
+        let boxed: Box<dyn VectorStore + Send + Sync> = Box::new(store);
+        exercise(&boxed).await;
+        let query = QueryRequest{vector: Some(vec![1.0, 0.0]), top_k: 1, ..Default::default()};
+        assert!(boxed.query(query).await.unwrap().matches.is_empty());
+    }
+}
@@ -0,0 +1,130 @@
+//! A local http server used by the tests of this crate to stand in for Pinecone.
+
+use std::{
+    convert::Infallible,
+    net::SocketAddr,
+    sync::{Arc, Mutex}
+};
+
+use hyper::{Body, Request, Response, Server, server::conn::AddrStream, service::{make_service_fn, service_fn}};
+use reqwest::{Method, StatusCode, header::HeaderMap};
+
+/// A response sent back by the [`Stub`].
+#[derive(Clone)]
+pub(crate) struct StubResponse {
+    pub(crate) status: StatusCode,
+    pub(crate) headers: Vec<(&'static str, String)>,
+    pub(crate) body: String
+}
+
+impl StubResponse {
+
+    pub(crate) fn new(status: StatusCode, body: impl Into<String>) -> StubResponse {
+        StubResponse {
+            status,
+            headers: Vec::new(),
+            body: body.into()
+        }
+    }
+
+    pub(crate) fn header(mut self, name: &'static str, value: impl Into<String>) -> StubResponse {
+        self.headers.push((name, value.into()));
+        self
+    }
+}
+
+/// A request received by the [`Stub`].
+#[derive(Clone, Debug)]
+pub(crate) struct StubRequest {
+    pub(crate) method: Method,
+    pub(crate) path: String,
+    pub(crate) headers: HeaderMap,
+    pub(crate) body: String,
+    /// Address of the connection the request was sent over.
+    pub(crate) remote: SocketAddr
+}
+
+pub(crate) struct Stub {
+    addr: SocketAddr,
+    requests: Arc<Mutex<Vec<StubRequest>>>
+}
+
+impl Stub {
+
+    /// Starts a server answering every request with `handler`, which is given the number of
+    /// requests received before it alongside the request itself.
+    pub(crate) fn start<F>(handler: F) -> Stub
+    where
+        F: Fn(usize, &StubRequest) -> StubResponse + Send + Sync + 'static
+    {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        let handler = Arc::new(handler);
+        let make_svc = make_service_fn(move |conn: &AddrStream| {
+            let remote = conn.remote_addr();
+            let recorded = recorded.clone();
+            let handler = handler.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req: Request<Body>| {
+                    let recorded = recorded.clone();
+                    let handler = handler.clone();
+                    async move {
+                        let (parts, body) = req.into_parts();
+                        let body = hyper::body::to_bytes(body).await?;
+                        let request = StubRequest {
+                            method: parts.method,
+                            path: parts.uri.path_and_query().map(|p| p.to_string()).unwrap_or_default(),
+                            headers: parts.headers,
+                            body: String::from_utf8_lossy(&body).to_string(),
+                            remote
+                        };
+                        let hit = {
+                            let mut recorded = recorded.lock().unwrap();
+                            recorded.push(request.clone());
+                            recorded.len() - 1
+                        };
+                        let resp = handler(hit, &request);
+                        let mut builder = Response::builder().status(resp.status);
+                        for (name, value) in resp.headers {
+                            builder = builder.header(name, value);
+                        }
+                        Ok::<_, hyper::Error>(builder.body(Body::from(resp.body)).unwrap())
+                    }
+                }))
+            }
+        });
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_svc);
+        let addr = server.local_addr();
+        tokio::spawn(server);
+        Stub{addr, requests}
+    }
+
+    /// Answers with `script` in order, repeating the last response once the script runs out.
+    pub(crate) fn script(script: Vec<StubResponse>) -> Stub {
+        Stub::start(move |hit, _| script[hit.min(script.len() - 1)].clone())
+    }
+
+    /// Answers requests matching a method and path, without the query, responding with a 404
+    /// otherwise.
+    pub(crate) fn routes(routes: Vec<(Method, &'static str, StubResponse)>) -> Stub {
+        Stub::start(move |_, req| {
+            let path = req.path.split('?').next().unwrap_or_default();
+            routes.iter()
+                .find(|(method, route, _)| *method == req.method && *route == path)
+                .map(|(_, _, resp)| resp.clone())
+                .unwrap_or_else(|| StubResponse::new(StatusCode::NOT_FOUND, ""))
+        })
+    }
+
+    pub(crate) fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    pub(crate) fn requests(&self) -> Vec<StubRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    pub(crate) fn hits(&self) -> usize {
+        self.requests.lock().unwrap().len()
+    }
+}
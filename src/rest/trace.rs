@@ -0,0 +1,185 @@
+//! Spans recorded around every request when the `tracing` feature is enabled.
+
+use std::future::Future;
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+use reqwest::{Method, Response};
+use tracing::{field::Empty, Instrument};
+
+use crate::Result;
+use super::{Connection, Operation};
+
+/// Runs `send` within a `pinecone_request` span and records the status, latency and number of
+/// retries of the request once it completes. `send` resolves to the result of the request
+/// alongside the number of times it was retried.
+///
+/// Only the fields below are recorded, the request headers, which hold the api key, are not.
+pub(crate) async fn instrument<C, F>(con: &C, operation: Operation<'_>, method: &Method, send: F) -> Result<Response>
+where
+    C: Connection,
+    F: Future<Output = (Result<Response>, u32)>
+{
+    let span = tracing::info_span!(
+        "pinecone_request",
+        operation = operation.name,
+        method = %method,
+        index = con.index_name(),
+        namespace = operation.namespace,
+        vector_count = operation.vectors,
+        top_k = operation.top_k,
+        status = Empty,
+        latency_ms = Empty,
+        retries = Empty
+    );
+    let start = Instant::now();
+    let (result, retries) = send.instrument(span.clone()).await;
+    span.record("latency_ms", start.elapsed().as_millis() as u64);
+    span.record("retries", retries);
+    match result {
+        Ok(ref resp) => {
+            span.record("status", resp.status().as_u16());
+        },
+        Err(ref err) => {
+            span.in_scope(|| tracing::warn!(error = %err, "request failed"));
+        }
+    }
+    result
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod trace_test {
+
+    use std::{collections::HashMap, fmt, sync::{Arc, Mutex}, time::Duration};
+
+    use reqwest::StatusCode;
+    use tracing::{field::{Field, Visit}, span::{Attributes, Id, Record}, Subscriber};
+    use tracing_subscriber::{layer::{Context, SubscriberExt}, Layer, Registry};
+
+    use crate::{
+        RetryPolicy,
+        models::{IndexCreateRequest, QueryRequest},
+        rest::{mock::MockPinecone, stub::{Stub, StubResponse}}
+    };
+
+    /// The fields recorded on a span, keyed by name.
+    type Recorded = HashMap<String, String>;
+
+    /// The fields recorded on every `pinecone_request` span with its id, in the order the spans
+    /// were created.
+    #[derive(Clone, Default)]
+    struct Spans(Arc<Mutex<Vec<(Id, Recorded)>>>);
+
+    struct Fields<'a>(&'a mut HashMap<String, String>);
+
+    impl Visit for Fields<'_> {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.insert(field.name().to_string(), value.to_string());
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0.insert(field.name().to_string(), format!("{:?}", value));
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for Spans {
+        fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, _: Context<'_, S>) {
+            if attrs.metadata().name() == "pinecone_request" {
+                let mut fields = HashMap::new();
+                attrs.record(&mut Fields(&mut fields));
+                self.0.lock().unwrap().push((id.clone(), fields));
+            }
+        }
+
+        fn on_record(&self, id: &Id, values: &Record<'_>, _: Context<'_, S>) {
+            if let Some((_, fields)) = self.0.lock().unwrap().iter_mut().find(|(span, _)| span == id) {
+                values.record(&mut Fields(fields));
+            }
+        }
+    }
+
+    impl Spans {
+        fn last(&self) -> Recorded {
+            self.0.lock().unwrap().last().map(|(_, fields)| fields.clone()).unwrap()
+        }
+    }
+
+    #[test]
+    fn test_spans_record_by_id() {
+        let spans = Spans::default();
+        let _guard = tracing::subscriber::set_default(Registry::default().with(spans.clone()));
+        let first = tracing::info_span!("pinecone_request", operation = "fetch", status = tracing::field::Empty);
+        let _second = tracing::info_span!("pinecone_request", operation = "query", status = tracing::field::Empty);
+        let other = tracing::info_span!("other", status = tracing::field::Empty);
+        first.record("status", 200);
+        other.record("status", 500);
+        let recorded = spans.0.lock().unwrap();
+        assert_eq!(recorded[0].1["status"], "200");
+        assert!(!recorded[1].1.contains_key("status"));
+    }
+
+    #[tokio::test]
+    async fn test_query_span() {
+        let spans = Spans::default();
+        let _guard = tracing::subscriber::set_default(Registry::default().with(spans.clone()));
+        let mock = MockPinecone::start().await;
+        let client = mock.client().await.unwrap();
+        client.create_index(IndexCreateRequest::builder("pinenut", 4).build().unwrap()).await.unwrap();
+        let index = client.index("pinenut");
+        index.upsert("tenant".to_string(), vec![crate::models::Vector{
+            id: "a".to_string(),
+            values: vec![0.1; 4],
+            sparse_values: None,
+            metadata: None
+        }]).await.unwrap();
+        let upsert = spans.last();
+        assert_eq!(upsert["operation"], "upsert");
+        assert_eq!(upsert["vector_count"], "1");
+
+        index.query(QueryRequest{
+            namespace: Some("tenant".to_string()),
+            top_k: 3,
+            filter: None,
+            include_values: false,
+            include_metadata: false,
+            vector: Some(vec![0.1; 4]),
+            sparse_vector: None,
+            id: None
+        }).await.unwrap();
+        let query = spans.last();
+        assert_eq!(query["operation"], "query");
+        assert_eq!(query["method"], "POST");
+        assert_eq!(query["index"], "pinenut");
+        assert_eq!(query["namespace"], "tenant");
+        assert_eq!(query["top_k"], "3");
+        assert_eq!(query["status"], "200");
+        assert_eq!(query["retries"], "0");
+        assert!(query.contains_key("latency_ms"));
+        assert!(!query.contains_key("vector_count"));
+
+        let recorded = format!("{:?}", spans.0.lock().unwrap());
+        assert!(!recorded.contains(mock.api_key()));
+    }
+
+    #[tokio::test]
+    async fn test_records_retries() {
+        let spans = Spans::default();
+        let _guard = tracing::subscriber::set_default(Registry::default().with(spans.clone()));
+        let mock = MockPinecone::start().await;
+        let stub = Stub::script(vec![
+            StubResponse::new(StatusCode::SERVICE_UNAVAILABLE, ""),
+            StubResponse::new(StatusCode::OK, r#"{"namespaces":{},"dimension":4,"indexFullness":0,"totalVectorCount":0}"#)
+        ]);
+        let client = mock.client().await.unwrap()
+            .with_retry_policy(RetryPolicy{base_delay: Duration::from_millis(1), ..Default::default()});
+        client.index_by_host("stubbed", stub.url()).describe_stats().await.unwrap();
+        let stats = spans.last();
+        assert_eq!(stats["operation"], "describe_index_stats");
+        assert_eq!(stats["index"], "stubbed");
+        assert_eq!(stats["status"], "200");
+        assert_eq!(stats["retries"], "1");
+    }
+}
@@ -0,0 +1,41 @@
+//! Types used to copy a namespace to and from a [JSON Lines](https://jsonlines.org) file, see
+//! [`Index::export_jsonl`] and [`Index::import_jsonl`].
+//!
+//! Every line of an export holds one [`Vector`] encoded as json, with its values, sparse values and
+//! metadata, so exports can be inspected with common tools and edited before being imported into
+//! another index or namespace.
+//!
+//! [`Index::export_jsonl`]: crate::Index::export_jsonl
+//! [`Index::import_jsonl`]: crate::Index::import_jsonl
+//! [`Vector`]: crate::models::Vector
+
+/// The number of ids fetched at a time by default, kept low so that the fetch url stays short.
+pub const DEFAULT_FETCH_SIZE: usize = 100;
+
+/// Controls how the vectors of a namespace are read during an export.
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    /// Number of ids listed at a time, Pinecone defaults to 100.
+    pub page_size: Option<usize>,
+    /// Maximum number of vectors requested by a single fetch.
+    pub fetch_size: usize
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        ExportOptions {
+            page_size: None,
+            fetch_size: DEFAULT_FETCH_SIZE
+        }
+    }
+}
+
+/// Progress of an export or import, reported after every fetch or upsert.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// Number of vectors written to or read from the file so far.
+    pub vectors: usize,
+    /// Number of vectors expected in total, when known. Exports use the namespace stats, which
+    /// Pinecone updates eventually, so this is an estimate.
+    pub total: Option<usize>
+}
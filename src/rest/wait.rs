@@ -0,0 +1,80 @@
+use std::{future::Future, time::Duration};
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+use crate::{Error, Result};
+use super::retry;
+
+/// Controls how often, and for how long, an index or collection is polled while waiting for an
+/// operation to finish. See [`Index::wait_until_ready`] and [`Client::wait_until_index_deleted`].
+///
+/// [`Index::wait_until_ready`]: crate::Index::wait_until_ready
+/// [`Client::wait_until_index_deleted`]: crate::Client::wait_until_index_deleted
+#[derive(Debug, Clone)]
+pub struct WaitOptions {
+    /// How long to wait before giving up with an [`Error::WaitTimeout`].
+    pub timeout: Duration,
+    /// Delay between the first two polls, doubled after every poll.
+    pub initial_delay: Duration,
+    /// Upper bound on the delay between two polls.
+    pub max_delay: Duration
+}
+
+impl Default for WaitOptions {
+    fn default() -> Self {
+        WaitOptions {
+            timeout: Duration::from_secs(300),
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(10)
+        }
+    }
+}
+
+impl WaitOptions {
+
+    /// Default options waiting for at most `timeout`.
+    pub fn with_timeout(timeout: Duration) -> WaitOptions {
+        WaitOptions {
+            timeout,
+            ..Default::default()
+        }
+    }
+}
+
+/// The outcome of a single poll.
+pub(crate) enum Poll<T> {
+    /// The operation finished.
+    Done(T),
+    /// The operation is still going, with a description of its current state.
+    Pending(String)
+}
+
+/// Calls `check` until it is [`Poll::Done`], errors, or `options.timeout` runs out, backing off
+/// exponentially between calls.
+pub(crate) async fn poll<T, F, Fut>(name: &str, options: &WaitOptions, mut check: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<Poll<T>>>
+{
+    let start = Instant::now();
+    let mut delay = options.initial_delay;
+    loop {
+        let state = match check().await? {
+            Poll::Done(value) => return Ok(value),
+            Poll::Pending(state) => state
+        };
+        let waited = start.elapsed();
+        if waited >= options.timeout {
+            return Err(Error::WaitTimeout {
+                name: name.to_string(),
+                state,
+                waited
+            });
+        }
+        retry::sleep(delay.min(options.timeout - waited)).await;
+        delay = delay.saturating_mul(2).min(options.max_delay);
+    }
+}
@@ -1,19 +1,15 @@
 extern crate pinenut;
 
 use pinenut::{models::Vector, Client};
+#[cfg(not(target_arch = "wasm32"))]
+use pinenut::{mock::MockPinecone, models::IndexCreateRequest};
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen_test::wasm_bindgen_test;
 
-#[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
-#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
-async fn index_upsert() {
-    // We create an instance of client first and firstmost. Panics if it couldn't authenticate.
-    let client = Client::new(env!("PINECONE_API_KEY"), env!("PINECONE_ENV"))
-        .await
-        .unwrap();
-
+/// Upserts a vector to `index`, taking its dimension from a describe.
+async fn upsert(client: &Client, index: &str) {
     // creates an index, will not authenticate.
-    let mut index = client.index(env!("PINECONE_INDEX_NAME"));
+    let index = client.index(index);
 
     // We use describe as a form of authenticate, panicing if we couldn't authenticate.
     let desc = index.describe().await.unwrap();
@@ -24,8 +20,32 @@ async fn index_upsert() {
         metadata: None,
     };
 
-    match index.upsert(String::from("odle"), vec![vec]).await {
-        Ok(_) => assert!(true),
-        Err(err) => panic!("unable to upsert: {:?}", err),
-    }
+    let response = index.upsert(String::from("odle"), vec![vec]).await.unwrap();
+    assert_eq!(response.upserted_count, 1);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[tokio::test]
+async fn index_upsert() {
+    // A local stand-in for Pinecone, kept alive for as long as the client is used.
+    let mock = MockPinecone::start().await;
+    // We create an instance of client first and firstmost. Panics if it couldn't authenticate.
+    let client = mock.client().await.unwrap();
+    client
+        .create_index(IndexCreateRequest::builder("pinenut", 32).build().unwrap())
+        .await
+        .unwrap();
+    upsert(&client, "pinenut").await;
+}
+
+/// The mock can't run on wasm, so this upserts to the index named by `PINECONE_INDEX_NAME` in a
+/// real project, the api key and environment being read when the test is built.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen_test]
+async fn index_upsert() {
+    // We create an instance of client first and firstmost. Panics if it couldn't authenticate.
+    let client = Client::new(env!("PINECONE_API_KEY"), env!("PINECONE_ENV"))
+        .await
+        .unwrap();
+    upsert(&client, env!("PINECONE_INDEX_NAME")).await;
 }